@@ -7,6 +7,48 @@ use leptos_router::{
 use leptos::task::spawn_local;
 use serde::{Deserialize, Serialize};
 
+/// Swaps in a `stats_alloc`-instrumented global allocator when the
+/// `instrumented` feature is enabled, so `AllocGuard` can log allocated,
+/// deallocated and residual bytes around each server function without
+/// touching release builds. Peak usage within a call is not tracked.
+#[cfg(feature = "instrumented")]
+#[global_allocator]
+static ALLOC: stats_alloc::StatsAlloc<std::alloc::System> = stats_alloc::INSTRUMENTED_SYSTEM;
+
+/// RAII guard that snapshots allocator stats on creation and logs the delta
+/// (bytes allocated/deallocated/residual) when it goes out of scope. Drop one
+/// of these in at the top of a server function to see which array sizes blow
+/// the memory budget.
+#[cfg(feature = "instrumented")]
+struct AllocGuard {
+    label: &'static str,
+    region: stats_alloc::Region<'static, std::alloc::System>,
+}
+
+#[cfg(feature = "instrumented")]
+impl AllocGuard {
+    fn new(label: &'static str) -> Self {
+        Self {
+            label,
+            region: stats_alloc::Region::new(&ALLOC),
+        }
+    }
+}
+
+#[cfg(feature = "instrumented")]
+impl Drop for AllocGuard {
+    fn drop(&mut self) {
+        let stats = self.region.change();
+        println!(
+            "[alloc:{}] allocated={}B deallocated={}B residual={}B",
+            self.label,
+            stats.bytes_allocated,
+            stats.bytes_deallocated,
+            stats.bytes_allocated as i64 - stats.bytes_deallocated as i64,
+        );
+    }
+}
+
 /// The parsed contents of a .npy file: a flat data buffer plus its shape.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NpyData {
@@ -16,144 +58,950 @@ pub struct NpyData {
     pub shape: Vec<u64>,
 }
 
-/// Server function that runs a TensorFlow model on input data via a Python script.
-/// 
-/// Workflow:
-/// 1. Loads the input .npy file from disk
-/// 2. Writes it to "user_input.npy"
-/// 3. Calls the Python script: python3 run_model.py
-/// 4. Reads the output from "output.npy" that the Python script generates
-/// 5. Returns the results as NpyData
+/// Current version of the exported scene document format. Bump this whenever
+/// `SceneDocument`'s shape changes and extend `migrate_scene_document` so
+/// older exports keep loading.
+const SCENE_FORMAT_VERSION: u32 = 1;
+
+/// Camera position/target as reported by (and re-applied to) `init_scene`'s
+/// three.js camera.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CameraPose {
+    pub position: [f32; 3],
+    pub target: [f32; 3],
+}
+
+/// The subset of settings-panel state worth round-tripping with a scene.
+/// `model_path` isn't here: the single-model "Run Model" flow always uses the
+/// same hardcoded Keras model, so there's no per-scene selection to capture.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneSettings {
+    pub galaxy_count: String,
+}
+
+/// A complete, versioned snapshot of what the user is looking at: the density
+/// grid (as a base64-encoded `.npy` so export/import is lossless), the
+/// opacities derived from it, the camera pose, and the settings that produced
+/// the grid. Exported as one JSON document via "Export Scene".
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SceneDocument {
+    pub format_version: u32,
+    pub density_npy_base64: String,
+    pub shape: Vec<u64>,
+    pub opacities: Vec<f32>,
+    pub camera: Option<CameraPose>,
+    pub settings: SceneSettings,
+}
+
+/// Brings an older `SceneDocument` JSON payload up to the current format.
+/// `format_version` 1 is current, so a v1 document passes through unchanged;
+/// one with no version is stamped as v1, and one newer than this build is
+/// rejected. Later bumps
+/// should pattern-match on the stored version and fill in fields that didn't
+/// exist yet rather than rejecting the file outright.
+fn migrate_scene_document(mut value: serde_json::Value) -> Result<serde_json::Value, String> {
+    let version = value.get("format_version").and_then(|v| v.as_u64()).unwrap_or(0);
+    if version > SCENE_FORMAT_VERSION as u64 {
+        return Err(format!("scene format v{version} is newer than this build supports (v{SCENE_FORMAT_VERSION})"));
+    }
+    if version < 1 {
+        // Pre-versioning exports never existed for this feature, but keep the
+        // branch as the template for the next real migration.
+        if let Some(obj) = value.as_object_mut() {
+            obj.insert("format_version".to_string(), serde_json::json!(1));
+        }
+    }
+    Ok(value)
+}
+
+#[cfg(not(feature = "ssr"))]
+fn encode_npy_base64(shape: &[u64], data: &[f32]) -> Result<String, String> {
+    use base64::Engine;
+    use npyz::WriterBuilder;
+
+    let mut bytes = Vec::new();
+    let mut writer = npyz::WriteOptions::<f32>::new()
+        .default_dtype()
+        .shape(shape)
+        .writer(&mut bytes)
+        .begin_nd()
+        .map_err(|e| format!("failed to begin npy writer: {e}"))?;
+    writer.extend(data.iter().cloned()).map_err(|e| format!("failed to write npy data: {e}"))?;
+    writer.finish().map_err(|e| format!("failed to finish npy writer: {e}"))?;
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+/// Best-effort mirror of the opacity normalization `set_opacities_from_densities`
+/// applies on the three.js side. The live opacities are GPU/JS state that
+/// can't be read back, so scene export recomputes an equivalent array from
+/// the density grid instead.
+#[cfg(not(feature = "ssr"))]
+fn densities_to_opacities(data: &[f32]) -> Vec<f32> {
+    let max = data.iter().cloned().fold(0f32, f32::max).max(1e-6);
+    data.iter().map(|&d| (d / max).clamp(0.0, 1.0)).collect()
+}
+
+#[cfg(not(feature = "ssr"))]
+fn decode_npy_base64(encoded: &str) -> Result<NpyData, String> {
+    use base64::Engine;
+    use npyz::NpyFile;
+
+    let bytes = base64::engine::general_purpose::STANDARD.decode(encoded).map_err(|e| e.to_string())?;
+    let npy = NpyFile::new(&bytes[..]).map_err(|e| e.to_string())?;
+    let shape = npy.shape().to_vec();
+    let data = npy.into_vec::<f32>().map_err(|e| e.to_string())?;
+    Ok(NpyData { data, shape })
+}
+
+/// How many decoded frames `FrameCache` keeps resident at once. Scrubbing past
+/// this many distinct steps evicts the least-recently-used frame rather than
+/// growing unbounded - large grids add up fast in WASM's linear memory.
+const MAX_RESIDENT_FRAMES: usize = 8;
+
+/// In-memory cache of decoded temporal-playback frames, keyed by step index,
+/// with simple LRU eviction once `MAX_RESIDENT_FRAMES` is exceeded. Evicted
+/// frames are re-fetched from the server (cheap - `get_job_output` just
+/// rereads the already-computed `output.npy`) rather than recomputed.
+#[derive(Clone, Default)]
+pub struct FrameCache {
+    frames: std::collections::HashMap<usize, NpyData>,
+    lru_order: std::collections::VecDeque<usize>,
+}
+
+impl FrameCache {
+    fn touch(&mut self, step: usize) {
+        self.lru_order.retain(|&s| s != step);
+        self.lru_order.push_back(step);
+    }
+
+    fn get(&mut self, step: usize) -> Option<NpyData> {
+        let data = self.frames.get(&step).cloned();
+        if data.is_some() {
+            self.touch(step);
+        }
+        data
+    }
+
+    fn insert(&mut self, step: usize, data: NpyData) {
+        if !self.frames.contains_key(&step) && self.frames.len() >= MAX_RESIDENT_FRAMES {
+            if let Some(oldest) = self.lru_order.pop_front() {
+                self.frames.remove(&oldest);
+            }
+        }
+        self.frames.insert(step, data);
+        self.touch(step);
+    }
+}
+
+/// Linearly interpolates between two same-length density grids so playback
+/// looks smooth even with only a handful of snapshots.
+fn lerp_densities(a: &[f32], b: &[f32], t: f32) -> Vec<f32> {
+    a.iter().zip(b).map(|(&x, &y)| x + (y - x) * t).collect()
+}
+
+/// Metadata for the paper backing this simulation, resolved either from the
+/// arXiv Atom API (for arXiv IDs) or Crossref (for bare DOIs). Only the
+/// fields the citation card renders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CitationMetadata {
+    pub title: String,
+    pub authors: Vec<String>,
+    pub journal: String,
+    pub year: i64,
+    /// Display label for the identifier (e.g. "10.1007/..." or "arXiv:2401.01234").
+    pub doi: String,
+    /// Link target for the identifier (doi.org for DOIs, arxiv.org/abs for arXiv IDs).
+    pub url: String,
+    pub abstract_text: String,
+}
+
+/// True if `identifier` looks like an arXiv ID (`YYMM.NNNNN`, optionally
+/// prefixed with `arXiv:` and/or suffixed with a version like `v2`), as
+/// opposed to a bare DOI such as `10.1007/s11433-023-2192-9`.
+#[cfg(not(feature = "ssr"))]
+fn is_arxiv_id(identifier: &str) -> bool {
+    let id = identifier.trim().trim_start_matches("arXiv:").trim_start_matches("arxiv:");
+    let mut parts = id.splitn(2, '.');
+    match (parts.next(), parts.next()) {
+        (Some(a), Some(b)) => {
+            a.len() == 4
+                && a.chars().all(|c| c.is_ascii_digit())
+                && b.len() >= 4
+                && b.chars().take(4).all(|c| c.is_ascii_digit())
+        }
+        _ => false,
+    }
+}
+
+#[cfg(not(feature = "ssr"))]
+fn unescape_xml(s: &str) -> String {
+    s.replace("&amp;", "&").replace("&lt;", "<").replace("&gt;", ">").replace("&quot;", "\"").replace("&apos;", "'")
+}
+
+/// Strips HTML/JATS tags (e.g. the `<jats:p>` wrapper Crossref puts around
+/// abstracts) down to plain text.
+#[cfg(not(feature = "ssr"))]
+fn strip_tags(s: &str) -> String {
+    let mut out = String::new();
+    let mut in_tag = false;
+    for c in s.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    unescape_xml(out.trim())
+}
+
+/// Extracts the text content of the first `<tag>...</tag>` in `xml`, with any
+/// nested tags stripped. Good enough for the small, fixed set of elements we
+/// read out of an arXiv Atom entry; not a general XML parser.
+#[cfg(not(feature = "ssr"))]
+fn extract_xml_text(xml: &str, tag: &str) -> Option<String> {
+    let open = format!("<{tag}");
+    let start = xml.find(&open)?;
+    let tag_end = xml[start..].find('>')? + start + 1;
+    let close = format!("</{tag}>");
+    let end = xml[tag_end..].find(&close)? + tag_end;
+    Some(strip_tags(&xml[tag_end..end]))
+}
+
+#[cfg(not(feature = "ssr"))]
+fn parse_arxiv_atom(arxiv_id: &str, xml: &str) -> Option<CitationMetadata> {
+    let entry_start = xml.find("<entry>")?;
+    let entry = &xml[entry_start..];
+
+    let title = extract_xml_text(entry, "title")?.split_whitespace().collect::<Vec<_>>().join(" ");
+    let abstract_text =
+        extract_xml_text(entry, "summary").unwrap_or_default().split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let mut authors = Vec::new();
+    let mut rest = entry;
+    while let Some(author_start) = rest.find("<author>") {
+        let after = &rest[author_start + "<author>".len()..];
+        let author_end = after.find("</author>").unwrap_or(after.len());
+        if let Some(name) = extract_xml_text(&after[..author_end], "name") {
+            authors.push(name);
+        }
+        rest = &after[author_end..];
+    }
+
+    let year = extract_xml_text(entry, "published")
+        .and_then(|p| p.get(0..4).map(|y| y.to_string()))
+        .and_then(|y| y.parse::<i64>().ok())
+        .unwrap_or(0);
+
+    Some(CitationMetadata {
+        title,
+        authors,
+        journal: "arXiv".to_string(),
+        year,
+        doi: format!("arXiv:{arxiv_id}"),
+        url: format!("https://arxiv.org/abs/{arxiv_id}"),
+        abstract_text,
+    })
+}
+
+#[cfg(not(feature = "ssr"))]
+async fn fetch_arxiv_metadata(arxiv_id: &str) -> Result<CitationMetadata, String> {
+    let id = arxiv_id.trim_start_matches("arXiv:").trim_start_matches("arxiv:");
+    let url = format!("http://export.arxiv.org/api/query?id_list={id}");
+    let xml = reqwest::get(&url).await.map_err(|e| e.to_string())?.text().await.map_err(|e| e.to_string())?;
+    parse_arxiv_atom(id, &xml).ok_or_else(|| "malformed arXiv Atom response".to_string())
+}
+
+#[cfg(not(feature = "ssr"))]
+fn parse_crossref_work(doi: &str, body: &serde_json::Value) -> Option<CitationMetadata> {
+    let message = body.get("message")?;
+    let title = message.get("title")?.as_array()?.first()?.as_str()?.to_string();
+    let authors = message
+        .get("author")
+        .and_then(|a| a.as_array())
+        .map(|list| {
+            list.iter()
+                .filter_map(|a| {
+                    let given = a.get("given").and_then(|v| v.as_str()).unwrap_or("");
+                    let family = a.get("family").and_then(|v| v.as_str()).unwrap_or("");
+                    let name = format!("{given} {family}").trim().to_string();
+                    if name.is_empty() { None } else { Some(name) }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    let journal = message
+        .get("container-title")
+        .and_then(|v| v.as_array())
+        .and_then(|a| a.first())
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+    let year = message
+        .get("published")
+        .and_then(|v| v.get("date-parts"))
+        .and_then(|v| v.as_array())
+        .and_then(|a| a.first())
+        .and_then(|v| v.as_array())
+        .and_then(|a| a.first())
+        .and_then(|v| v.as_i64())
+        .unwrap_or(0);
+    let abstract_text = message.get("abstract").and_then(|v| v.as_str()).map(strip_tags).unwrap_or_default();
+    Some(CitationMetadata {
+        title,
+        authors,
+        journal,
+        year,
+        doi: doi.to_string(),
+        url: format!("https://doi.org/{doi}"),
+        abstract_text,
+    })
+}
+
+/// Fetches and caches citation metadata for a paper identifier, which may be
+/// an arXiv ID (resolved via the arXiv Atom API) or a bare DOI (resolved via
+/// Crossref). Runs client-side only; the About overlay falls back to the
+/// static citation text during SSR and whenever this fails.
+#[cfg(not(feature = "ssr"))]
+async fn fetch_citation_metadata(identifier: &str) -> Result<CitationMetadata, String> {
+    thread_local! {
+        static CITATION_CACHE: std::cell::RefCell<std::collections::HashMap<String, CitationMetadata>> =
+            std::cell::RefCell::new(std::collections::HashMap::new());
+    }
+
+    if let Some(cached) = CITATION_CACHE.with(|c| c.borrow().get(identifier).cloned()) {
+        return Ok(cached);
+    }
+
+    let metadata = if is_arxiv_id(identifier) {
+        fetch_arxiv_metadata(identifier).await?
+    } else {
+        let url = format!("https://api.crossref.org/works/{identifier}");
+        let body: serde_json::Value =
+            reqwest::get(&url).await.map_err(|e| e.to_string())?.json().await.map_err(|e| e.to_string())?;
+        parse_crossref_work(identifier, &body).ok_or_else(|| "malformed Crossref response".to_string())?
+    };
+
+    CITATION_CACHE.with(|c| c.borrow_mut().insert(identifier.to_string(), metadata.clone()));
+    Ok(metadata)
+}
+
+/// Where inference for a "Run Model" click actually happens: the existing
+/// server round-trip through `run_model`, or in-browser via WebGPU. Selected
+/// in the settings panel and read at the `run_model` call site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InferenceBackend {
+    Server,
+    WebGpu,
+}
+
+impl Default for InferenceBackend {
+    fn default() -> Self {
+        InferenceBackend::Server
+    }
+}
+
+/// One entry in the model registry: a training checkpoint `run_model` can be
+/// pointed at instead of the hardcoded `"model_final.keras"`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelRegistryEntry {
+    pub id: String,
+    pub display_name: String,
+    pub path: String,
+    pub expected_shape: Vec<u64>,
+}
+
+/// The available models, newest checkpoint first. A real deployment might
+/// load this from config; it's a fixed list here since the checkpoints
+/// themselves are baked into the deployment alongside `model_final.keras`.
+fn model_registry() -> Vec<ModelRegistryEntry> {
+    vec![
+        ModelRegistryEntry {
+            id: "model_final".to_string(),
+            display_name: "Final (production)".to_string(),
+            path: "model_final.keras".to_string(),
+            expected_shape: vec![64, 64, 64],
+        },
+        ModelRegistryEntry {
+            id: "model_v2".to_string(),
+            display_name: "Checkpoint v2".to_string(),
+            path: "model_v2.keras".to_string(),
+            expected_shape: vec![64, 64, 64],
+        },
+        ModelRegistryEntry {
+            id: "model_v1".to_string(),
+            display_name: "Checkpoint v1".to_string(),
+            path: "model_v1.keras".to_string(),
+            expected_shape: vec![64, 64, 64],
+        },
+    ]
+}
+
+/// How two compared models' outputs get rendered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ComparisonRenderMode {
+    /// Two synced `DarkMatterScene` canvases, one per model, sharing a camera.
+    SplitView,
+    /// A single canvas showing the per-voxel absolute difference.
+    Diff,
+}
+
+impl Default for ComparisonRenderMode {
+    fn default() -> Self {
+        ComparisonRenderMode::SplitView
+    }
+}
+
+#[cfg(not(feature = "ssr"))]
+fn diff_densities(a: &[f32], b: &[f32]) -> Vec<f32> {
+    a.iter().zip(b).map(|(&x, &y)| (x - y).abs()).collect()
+}
+
+/// Unique identifier for one inference job. Opaque to the client; just threaded
+/// back through `poll_job` and `run_model`.
+pub type JobId = String;
+
+/// Where a job currently stands, returned to the client by `poll_job` so the UI
+/// can show real progress instead of a static "loading" string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done { shape: Vec<u64> },
+    Failed { error: String },
+}
+
+#[cfg(feature = "ssr")]
+struct JobState {
+    status: JobStatus,
+}
+
+/// In-memory job table, keyed by `JobId`. Lives for the process lifetime; jobs
+/// aren't persisted across server restarts, which is fine since the underlying
+/// `jobs/{job_id}/*.npy` files are themselves scratch space for one session.
+#[cfg(feature = "ssr")]
+static JOBS: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<JobId, JobState>>> =
+    std::sync::OnceLock::new();
+
+#[cfg(feature = "ssr")]
+fn jobs() -> &'static std::sync::Mutex<std::collections::HashMap<JobId, JobState>> {
+    JOBS.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+/// Caps how many `run_model` inferences run at once so a burst of clicks across
+/// concurrent browser sessions can't all pile onto the GIL / GPU at the same time.
+#[cfg(feature = "ssr")]
+static RUN_MODEL_SEMAPHORE: std::sync::OnceLock<tokio::sync::Semaphore> = std::sync::OnceLock::new();
+
+#[cfg(feature = "ssr")]
+fn run_model_semaphore() -> &'static tokio::sync::Semaphore {
+    RUN_MODEL_SEMAPHORE.get_or_init(|| tokio::sync::Semaphore::new(2))
+}
+
+#[cfg(feature = "ssr")]
+fn new_job_id() -> JobId {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static NEXT_JOB_SEQ: AtomicU64 = AtomicU64::new(1);
+    let seq = NEXT_JOB_SEQ.fetch_add(1, Ordering::Relaxed);
+    let nonce: u32 = rand::random();
+    format!("job-{seq:06}-{nonce:08x}")
+}
+
+#[cfg(feature = "ssr")]
+fn job_dir(job_id: &str) -> std::path::PathBuf {
+    std::path::Path::new("jobs").join(job_id)
+}
+
+#[cfg(feature = "ssr")]
+fn set_job_status(job_id: &str, status: JobStatus) {
+    jobs()
+        .lock()
+        .unwrap()
+        .entry(job_id.to_string())
+        .or_insert(JobState {
+            status: JobStatus::Queued,
+        })
+        .status = status;
+}
+
+/// Server function the client polls to find out how a `run_model` job is doing,
+/// so the UI can show real progress instead of a static "loading" string.
+#[server]
+pub async fn poll_job(job_id: JobId) -> Result<JobStatus, ServerFnError> {
+    let status = jobs()
+        .lock()
+        .unwrap()
+        .get(&job_id)
+        .map(|j| j.status.clone())
+        .ok_or_else(|| ServerFnError::new(format!("Unknown job_id: {}", job_id)))?;
+    Ok(status)
+}
+
+/// Holds one lazily-initialized, warm Python interpreter state per model file.
+///
+/// The `Py<PyAny>` is the loaded Keras model object; building it is the expensive
+/// part (importing TensorFlow + `keras.models.load_model`), so we keep it alive for the
+/// lifetime of the server process instead of paying that cost on every click.
+#[cfg(feature = "ssr")]
+struct LoadedModel {
+    model: pyo3::Py<pyo3::PyAny>,
+}
+
+/// `model_path` -> warm interpreter state. The mutex is held only while a model
+/// is looked up or loaded, not during `predict`. Keyed rather than a single slot because the
+/// UI lets users pick between checkpoints (see the model registry).
+#[cfg(feature = "ssr")]
+static LOADED_MODELS: std::sync::OnceLock<std::sync::Mutex<std::collections::HashMap<String, LoadedModel>>> =
+    std::sync::OnceLock::new();
+
+#[cfg(feature = "ssr")]
+fn loaded_models() -> &'static std::sync::Mutex<std::collections::HashMap<String, LoadedModel>> {
+    LOADED_MODELS.get_or_init(|| std::sync::Mutex::new(std::collections::HashMap::new()))
+}
+
+/// Loads (or reuses) the Keras model at `model_path` inside the held GIL, calls
+/// `predict` on `input` and returns the flattened output with its 3-D shape
+/// (the 5-D `PyArray5<f32>` result with its batch and channel dims dropped).
+#[cfg(feature = "ssr")]
+fn run_inference_in_python(
+    py: pyo3::Python<'_>,
+    model_path: &str,
+    input_data: &[f32],
+    input_shape: &[u64],
+) -> pyo3::PyResult<(Vec<f32>, Vec<u64>)> {
+    use numpy::{PyArray5, PyArrayMethods, ToPyArray};
+    use pyo3::prelude::*;
+
+    if input_shape.len() != 3 {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "expected a 3-D input array, got shape {:?}",
+            input_shape
+        )));
+    }
+
+    // Load (or reuse) the model, then clone the GIL-independent handle and
+    // drop the map lock before calling `predict` - holding the lock across
+    // the call would serialize every job process-wide regardless of
+    // `model_path`, defeating `RUN_MODEL_SEMAPHORE`'s concurrency of 2.
+    let model: pyo3::Py<pyo3::PyAny> = {
+        let mut models = loaded_models().lock().unwrap();
+        if !models.contains_key(model_path) {
+            println!("[run_model] Cold start: loading Keras model from {}", model_path);
+            let keras = py.import("tensorflow.keras")?;
+            let model = keras
+                .getattr("models")?
+                .call_method1("load_model", (model_path,))?;
+            models.insert(
+                model_path.to_string(),
+                LoadedModel {
+                    model: model.unbind(),
+                },
+            );
+        } else {
+            println!("[run_model] Reusing warm interpreter + weights for {}", model_path);
+        }
+        models.get(model_path).unwrap().model.clone_ref(py)
+    };
+    let model = model.bind(py);
+
+    let (dx, dy, dz) = (
+        input_shape[0] as usize,
+        input_shape[1] as usize,
+        input_shape[2] as usize,
+    );
+    // Keras's Conv3D UNet expects 5-D (batch, D, H, W, channels) input and
+    // returns the same, matching what the old subprocess `run_model.py` did
+    // before handing data to `predict`; add those dims here and squeeze them
+    // back off the result.
+    let input_array = ndarray::Array5::from_shape_vec((1, dx, dy, dz, 1), input_data.to_vec())
+        .map_err(|e| pyo3::exceptions::PyValueError::new_err(e.to_string()))?;
+    let py_input: Bound<'_, PyArray5<f32>> = input_array.to_pyarray(py);
+
+    let py_output = model.call_method1("predict", (py_input,))?;
+    let py_output: Bound<'_, PyArray5<f32>> = py_output.extract()?;
+    let out_shape_5d: Vec<u64> = py_output.shape().iter().map(|&d| d as u64).collect();
+    let data = py_output.to_owned_array().into_raw_vec();
+    let out_shape = out_shape_5d[1..4].to_vec();
+
+    Ok((data, out_shape))
+}
+
+/// Server function that runs a TensorFlow model on input data, in-process.
+///
+/// The model is loaded once per `model_path` into a warm embedded Python
+/// interpreter (via `pyo3` + `numpy`) and kept resident across calls, so
+/// repeated "Run Model" clicks skip process-spawn and model-load entirely.
+///
+/// Runs against the per-job input written by `save_galaxy_data` /
+/// `generate_npy_data` (`jobs/{job_id}/input.npy`) rather than a shared global
+/// path, so concurrent sessions can't clobber each other's data. A semaphore
+/// caps how many of these run at once across all jobs.
 ///
 /// # Arguments
-/// * `input_npy_path` - Path to the input .npy file (e.g., "run0100_dm.npy")
-/// * `model_path` - Path to the model file (not used by simplified version but kept for API compatibility)
-/// * `temp_output_path` - Not used in file-based version
+/// * `job_id` - Job previously created by `save_galaxy_data` or `generate_npy_data`
+/// * `model_path` - Path to the Keras model file, also the warm-interpreter cache key
 ///
 /// # Returns
 /// The inference output as NpyData (flattened array + shape)
 #[server]
-pub async fn run_model(
-    input_npy_path: String,
-    model_path: String,
-    temp_output_path: Option<String>,
-) -> Result<NpyData, ServerFnError> {
-    use std::process::Command;
-    use std::path::Path;
+pub async fn run_model(job_id: JobId, model_path: String) -> Result<NpyData, ServerFnError> {
     use npyz::NpyFile;
 
+    #[cfg(feature = "instrumented")]
+    let _alloc_guard = AllocGuard::new("run_model");
+
     println!("[run_model] ========================================");
     println!("[run_model] Called with:");
-    println!("[run_model]   input_npy_path: {}", input_npy_path);
+    println!("[run_model]   job_id: {}", job_id);
     println!("[run_model]   model_path: {}", model_path);
-    println!("[run_model]   temp_output_path: {:?}", temp_output_path);
     println!("[run_model] ========================================");
 
-    // Step 1: Read input file
-    println!("[run_model] STEP 1: Reading input file");
-    if !Path::new(&input_npy_path).exists() {
-        let err_msg = format!("Input file not found: {}", input_npy_path);
-        println!("[run_model] ERROR: {}", err_msg);
-        return Err(ServerFnError::new(err_msg));
-    }
+    let _permit = run_model_semaphore().acquire().await.map_err(|e| {
+        ServerFnError::new(format!("Failed to acquire run_model semaphore: {}", e))
+    })?;
+    set_job_status(&job_id, JobStatus::Running);
 
-    let input_bytes = std::fs::read(&input_npy_path)
-        .map_err(|e| {
+    let input_path = job_dir(&job_id).join("input.npy");
+
+    // Step 1: Read and parse the per-job input file. `save_galaxy_data` writes
+    // either a dense `input.npy` or, for resolution >= SPARSE_GRID_THRESHOLD,
+    // a sparse COO triple (`input_coords.npy` / `input_values.npy` /
+    // `input_resolution.npy`) with no `input.npy` at all - densify that case
+    // instead of failing the job.
+    println!("[run_model] STEP 1: Reading input file");
+    let (input_data, input_shape) = if input_path.exists() {
+        let input_bytes = std::fs::read(&input_path).map_err(|e| {
             let err_msg = format!("Failed to read input file: {}", e);
             println!("[run_model] ERROR: {}", err_msg);
+            set_job_status(&job_id, JobStatus::Failed { error: err_msg.clone() });
             ServerFnError::new(err_msg)
         })?;
-    println!("[run_model] Read {} bytes from {}", input_bytes.len(), input_npy_path);
 
-    // Step 2: Write to user_input.npy
-    println!("[run_model] STEP 2: Writing to user_input.npy");
-    std::fs::write("user_input.npy", &input_bytes)
-        .map_err(|e| {
-            let err_msg = format!("Failed to write user_input.npy: {}", e);
+        let input_npy = NpyFile::new(&input_bytes[..]).map_err(|e| {
+            let err_msg = format!("Failed to parse input npy: {}", e);
             println!("[run_model] ERROR: {}", err_msg);
+            set_job_status(&job_id, JobStatus::Failed { error: err_msg.clone() });
             ServerFnError::new(err_msg)
         })?;
-    println!("[run_model] Successfully wrote user_input.npy");
-
-    // Step 3: Execute Python script using venv
-    println!("[run_model] STEP 3: Executing python3 run_model.py");
-    println!("[run_model] ========================================");
-    
-    let output = Command::new(".venv/bin/python3")
-        .arg("run_model.py")
-        .output()
-        .map_err(|e| {
-            let err_msg = format!("Failed to execute python script: {}", e);
+        let input_shape = input_npy.shape().to_vec();
+        let input_data: Vec<f32> = input_npy.into_vec::<f32>().map_err(|e| {
+            let err_msg = format!("Failed to read input npy data as f32: {}", e);
             println!("[run_model] ERROR: {}", err_msg);
+            set_job_status(&job_id, JobStatus::Failed { error: err_msg.clone() });
             ServerFnError::new(err_msg)
         })?;
+        (input_data, input_shape)
+    } else if job_dir(&job_id).join("input_coords.npy").exists() {
+        println!("[run_model] No input.npy; densifying sparse COO input instead");
+        densify_sparse_input(&job_id).map_err(|e| {
+            let err_msg = format!("Failed to densify sparse input: {}", e);
+            println!("[run_model] ERROR: {}", err_msg);
+            set_job_status(&job_id, JobStatus::Failed { error: err_msg.clone() });
+            ServerFnError::new(err_msg)
+        })?
+    } else {
+        let err_msg = format!("Input file not found for job: {}", job_id);
+        println!("[run_model] ERROR: {}", err_msg);
+        set_job_status(&job_id, JobStatus::Failed { error: err_msg.clone() });
+        return Err(ServerFnError::new(err_msg));
+    };
+    println!(
+        "[run_model] Read {} f32 values with shape {:?}",
+        input_data.len(),
+        input_shape
+    );
 
-    let stderr = String::from_utf8_lossy(&output.stderr);
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    
-    println!("[run_model] Python script output:");
-    
-    if !stdout.is_empty() {
-        println!("[run_model] --- STDOUT ---");
-        println!("{}", stdout);
+    // Step 2: Run inference in-process, holding the GIL for the duration of the call
+    println!("[run_model] STEP 2: Running inference via embedded Python");
+    let (data, shape) = pyo3::Python::with_gil(|py| {
+        run_inference_in_python(py, &model_path, &input_data, &input_shape).map_err(|e| {
+            // Surface the Python traceback (not just the exception message) so
+            // failures in the model code are debuggable from the Rust side.
+            let traceback = e
+                .traceback(py)
+                .and_then(|tb| tb.format().ok())
+                .unwrap_or_default();
+            format!("{e}\n{traceback}")
+        })
+    })
+    .map_err(|err_msg| {
+        println!("[run_model] ERROR: Python inference failed: {}", err_msg);
+        set_job_status(&job_id, JobStatus::Failed { error: err_msg.clone() });
+        ServerFnError::new(format!("Python inference failed: {}", err_msg))
+    })?;
+
+    println!(
+        "[run_model] SUCCESS: Loaded {} f32 values with shape {:?}",
+        data.len(),
+        shape
+    );
+
+    // Persist the output grid alongside the job's input so later calls
+    // (e.g. `trace_filaments`) can load it without re-running inference.
+    if let Err(e) = write_npy(&job_dir(&job_id).join("output.npy"), &shape, &data) {
+        println!("[run_model] WARNING: Failed to persist output.npy for job {}: {}", job_id, e);
     }
-    
-    if !stderr.is_empty() {
-        println!("[run_model] --- STDERR ---");
-        println!("{}", stderr);
+
+    println!("[run_model] ========================================");
+    set_job_status(&job_id, JobStatus::Done { shape: shape.clone() });
+    Ok(NpyData { data, shape })
+}
+
+/// Why a streamed NPY fetch bailed out of the fast path.
+#[cfg(feature = "ssr")]
+enum NpyStreamError {
+    /// The file is in a shape the incremental parser doesn't handle (e.g.
+    /// Fortran order or a non-`<f4` dtype) - caller should fall back to a
+    /// plain buffered fetch + `npyz`.
+    Unsupported(String),
+    /// A real I/O or protocol failure; no point falling back.
+    Failed(String),
+}
+
+/// Parses the `{'descr': '<f4', 'fortran_order': False, 'shape': (64, 64, 64)}`
+/// style Python-literal header dict that NPY v1.0 embeds, pulling out just the
+/// two fields the streaming loader cares about.
+#[cfg(feature = "ssr")]
+fn parse_npy_header_dict(header: &str) -> Result<(String, Vec<u64>), String> {
+    let descr = header
+        .split("'descr':")
+        .nth(1)
+        .and_then(|rest| rest.split('\'').nth(1))
+        .ok_or_else(|| format!("couldn't find 'descr' in header: {header}"))?
+        .to_string();
+
+    let fortran_order = header
+        .split("'fortran_order':")
+        .nth(1)
+        .map(|rest| rest.trim_start().starts_with("True"))
+        .unwrap_or(false);
+    if fortran_order {
+        return Err("fortran_order is True".to_string());
     }
-    
-    if stdout.is_empty() && stderr.is_empty() {
-        println!("[run_model] (No output captured)");
+
+    let shape_str = header
+        .split("'shape':")
+        .nth(1)
+        .and_then(|rest| rest.split('(').nth(1))
+        .and_then(|rest| rest.split(')').next())
+        .ok_or_else(|| format!("couldn't find 'shape' in header: {header}"))?;
+    let shape: Vec<u64> = shape_str
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<u64>().map_err(|e| e.to_string()))
+        .collect::<Result<_, _>>()?;
+
+    Ok((descr, shape))
+}
+
+/// Drains complete little-endian `f32` groups out of `tail`, appending them to
+/// `data` and leaving any trailing partial group (0-3 bytes) in `tail` for the
+/// next chunk to complete.
+#[cfg(feature = "ssr")]
+fn drain_f32_le(data: &mut Vec<f32>, tail: &mut Vec<u8>) {
+    let usable = tail.len() - (tail.len() % 4);
+    for chunk in tail[..usable].chunks_exact(4) {
+        data.push(f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]));
     }
-    println!("[run_model] ========================================");
+    tail.drain(..usable);
+}
 
-    if !output.status.success() {
-        let err_msg = format!("Python script failed with exit code: {}", output.status);
-        println!("[run_model] ERROR: {}", err_msg);
-        return Err(ServerFnError::new(err_msg));
+/// Fetches a remote `.npy` file as a chunked body instead of buffering the
+/// whole response, parsing the NPY header as soon as enough bytes have
+/// arrived and filling the output buffer incrementally as chunks land. Calls
+/// `on_progress(bytes_received, content_length)` after every chunk so the
+/// caller can report download percentage.
+///
+/// Only handles the common little-endian `<f4`, C-order case; anything else
+/// returns `NpyStreamError::Unsupported` so the caller can fall back.
+#[cfg(feature = "ssr")]
+async fn fetch_npy_streamed(
+    url: &str,
+    on_progress: impl Fn(u64, Option<u64>),
+) -> Result<NpyData, NpyStreamError> {
+    use futures_util::StreamExt;
+
+    let resp = reqwest::get(url)
+        .await
+        .map_err(|e| NpyStreamError::Failed(format!("request to {url} failed: {e}")))?;
+    if !resp.status().is_success() {
+        return Err(NpyStreamError::Failed(format!(
+            "API returned non-success status: {}",
+            resp.status()
+        )));
     }
-    println!("[run_model] Python script executed successfully");
+    let content_length = resp.content_length();
+
+    let mut header_buf: Vec<u8> = Vec::new();
+    let mut shape: Option<Vec<u64>> = None;
+    let mut tail: Vec<u8> = Vec::new();
+    let mut data: Vec<f32> = Vec::new();
+    let mut received: u64 = 0;
+
+    let mut stream = resp.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| NpyStreamError::Failed(format!("stream error: {e}")))?;
+        received += chunk.len() as u64;
+        on_progress(received, content_length);
+
+        if shape.is_none() {
+            header_buf.extend_from_slice(&chunk);
+            // Magic (6) + version (2) + header_len (2) is the fixed preamble.
+            if header_buf.len() < 10 {
+                continue;
+            }
+            if &header_buf[0..6] != b"\x93NUMPY" {
+                return Err(NpyStreamError::Failed("not a valid NPY file (bad magic)".to_string()));
+            }
+            let header_len = u16::from_le_bytes([header_buf[8], header_buf[9]]) as usize;
+            let preamble_len = 10 + header_len;
+            if header_buf.len() < preamble_len {
+                continue;
+            }
 
-    // Step 4: Read output.npy
-    println!("[run_model] STEP 4: Reading output.npy");
-    if !Path::new("output.npy").exists() {
-        let err_msg = "Python script did not create output.npy file".to_string();
-        println!("[run_model] ERROR: {}", err_msg);
-        return Err(ServerFnError::new(err_msg));
+            let header_str = String::from_utf8_lossy(&header_buf[10..preamble_len]).into_owned();
+            let (descr, parsed_shape) =
+                parse_npy_header_dict(&header_str).map_err(NpyStreamError::Unsupported)?;
+            if descr != "<f4" {
+                return Err(NpyStreamError::Unsupported(format!("unsupported dtype: {descr}")));
+            }
+
+            let n_elems = parsed_shape.iter().product::<u64>() as usize;
+            data = Vec::with_capacity(n_elems);
+            tail = header_buf.split_off(preamble_len);
+            drain_f32_le(&mut data, &mut tail);
+            shape = Some(parsed_shape);
+        } else {
+            tail.extend_from_slice(&chunk);
+            drain_f32_le(&mut data, &mut tail);
+        }
     }
 
-    let output_bytes = std::fs::read("output.npy")
-        .map_err(|e| {
-            let err_msg = format!("Failed to read output.npy: {}", e);
-            println!("[run_model] ERROR: {}", err_msg);
-            ServerFnError::new(err_msg)
-        })?;
-    println!("[run_model] Read {} bytes from output.npy", output_bytes.len());
+    let shape = shape.ok_or_else(|| {
+        NpyStreamError::Failed("stream ended before the NPY header was fully received".to_string())
+    })?;
+    Ok(NpyData { data, shape })
+}
 
-    // Step 5: Parse output.npy
-    println!("[run_model] STEP 5: Parsing output.npy");
-    let npy = NpyFile::new(&output_bytes[..])
-        .map_err(|e| {
-            let err_msg = format!("Failed to parse output.npy: {}", e);
-            println!("[run_model] ERROR: {}", err_msg);
-            ServerFnError::new(err_msg)
-        })?;
+/// A memory-mapped `.npy` file with its header already validated, giving a
+/// zero-copy `&[f32]` view over the mapped data region.
+#[cfg(feature = "ssr")]
+pub struct NpyMmap {
+    mmap: memmap2::Mmap,
+    data_offset: usize,
+    pub shape: Vec<u64>,
+}
 
-    let shape = npy.shape().to_vec();
-    println!("[run_model] Parsed shape: {:?}", shape);
+#[cfg(feature = "ssr")]
+impl NpyMmap {
+    /// Zero-copy view of the array data as `f32`, valid for as long as `self` is.
+    pub fn as_f32_slice(&self) -> &[f32] {
+        bytemuck::cast_slice(&self.mmap[self.data_offset..])
+    }
+}
 
-    let data: Vec<f32> = npy
-        .into_vec::<f32>()
-        .map_err(|e| {
-            let err_msg = format!("Failed to read output.npy data as f32: {}", e);
-            println!("[run_model] ERROR: {}", err_msg);
-            ServerFnError::new(err_msg)
-        })?;
+/// Memory-maps `path` and validates its NPY header without copying the data
+/// region into a `Vec`, for the read-only visualization path where the full
+/// 64³ (or larger) grid would otherwise be re-vectored for no reason.
+///
+/// Only handles the common little-endian `<f4`, C-order case; callers should
+/// fall back to the existing `std::fs::read` + `npyz` path for anything else.
+#[cfg(feature = "ssr")]
+fn load_npy_mmap(path: &str) -> Result<NpyMmap, String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("failed to open {path}: {e}"))?;
+    // Safety: the mapped file is treated as read-only for the lifetime of `NpyMmap`,
+    // same tradeoff `memmap2` users accept elsewhere - a concurrent external write
+    // to `path` could observe/produce torn reads.
+    let mmap = unsafe { memmap2::Mmap::map(&file) }.map_err(|e| format!("failed to mmap {path}: {e}"))?;
+
+    if mmap.len() < 10 || &mmap[0..6] != b"\x93NUMPY" {
+        return Err("not a valid NPY file (bad magic)".to_string());
+    }
+    let header_len = u16::from_le_bytes([mmap[8], mmap[9]]) as usize;
+    let preamble_len = 10 + header_len;
+    if mmap.len() < preamble_len {
+        return Err("truncated NPY header".to_string());
+    }
 
-    println!("[run_model] SUCCESS: Loaded {} f32 values with shape {:?}", data.len(), shape);
-    println!("[run_model] ========================================");
-    Ok(NpyData { data, shape })
+    let header_str = String::from_utf8_lossy(&mmap[10..preamble_len]).into_owned();
+    let (descr, shape) = parse_npy_header_dict(&header_str)?;
+    if descr != "<f4" {
+        return Err(format!("unsupported dtype for mmap loader: {descr}"));
+    }
+
+    let expected_bytes = shape.iter().product::<u64>() as usize * 4;
+    if mmap.len() - preamble_len < expected_bytes {
+        return Err("NPY data region shorter than shape implies".to_string());
+    }
+
+    Ok(NpyMmap {
+        mmap,
+        data_offset: preamble_len,
+        shape,
+    })
+}
+
+/// Writes `data` as a `.npy` file at `path` for any `shape` and any dtype `T`
+/// supports, via `npyz`'s `WriterBuilder` (which handles the 64-byte-aligned
+/// header padding itself). Replaces the old hand-assembled `(64, 64, 64)`
+/// header that `save_galaxy_data` / `generate_npy_data` used to bake in.
+#[cfg(feature = "ssr")]
+fn write_npy<T: npyz::AutoSerialize + Clone>(
+    path: &std::path::Path,
+    shape: &[u64],
+    data: &[T],
+) -> Result<(), String> {
+    use npyz::WriterBuilder;
+
+    let file = std::fs::File::create(path).map_err(|e| format!("failed to create {}: {}", path.display(), e))?;
+    let mut writer = npyz::WriteOptions::<T>::new()
+        .default_dtype()
+        .shape(shape)
+        .writer(std::io::BufWriter::new(file))
+        .begin_nd()
+        .map_err(|e| format!("failed to begin npy writer for {}: {}", path.display(), e))?;
+    writer
+        .extend(data.iter().cloned())
+        .map_err(|e| format!("failed to write npy data to {}: {}", path.display(), e))?;
+    writer
+        .finish()
+        .map_err(|e| format!("failed to finish npy writer for {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+#[cfg(feature = "ssr")]
+fn read_npy_file<T: npyz::Deserialize>(path: &std::path::Path) -> Result<(Vec<T>, Vec<u64>), String> {
+    use npyz::NpyFile;
+
+    let bytes = std::fs::read(path).map_err(|e| format!("failed to read {}: {}", path.display(), e))?;
+    let npy = NpyFile::new(&bytes[..]).map_err(|e| format!("failed to parse {}: {}", path.display(), e))?;
+    let shape = npy.shape().to_vec();
+    let data = npy.into_vec::<T>().map_err(|e| format!("failed to read {} data: {}", path.display(), e))?;
+    Ok((data, shape))
+}
+
+/// Densifies the sparse COO input `save_galaxy_data` writes for
+/// `resolution >= SPARSE_GRID_THRESHOLD` (`input_coords.npy` + `input_values.npy`
+/// + `input_resolution.npy`) back into a dense grid the same shape as the
+/// dense branch would have written directly to `input.npy`, so `run_model`
+/// can service either form of job the same way.
+#[cfg(feature = "ssr")]
+fn densify_sparse_input(job_id: &str) -> Result<(Vec<f32>, Vec<u64>), String> {
+    let dir = job_dir(job_id);
+    let (flat_coords, _): (Vec<u64>, Vec<u64>) = read_npy_file(&dir.join("input_coords.npy"))?;
+    let (values, _): (Vec<f32>, Vec<u64>) = read_npy_file(&dir.join("input_values.npy"))?;
+    let (resolution_vec, _): (Vec<u64>, Vec<u64>) = read_npy_file(&dir.join("input_resolution.npy"))?;
+    let resolution = *resolution_vec.first().ok_or("input_resolution.npy was empty")?;
+
+    let total = (resolution * resolution * resolution) as usize;
+    let mut data = vec![-1.0f32; total];
+    for (coord, &density) in flat_coords.chunks_exact(3).zip(values.iter()) {
+        let (x, y, z) = (coord[0], coord[1], coord[2]);
+        let index = (x * resolution * resolution + y * resolution + z) as usize;
+        data[index] = density;
+    }
+    Ok((data, vec![resolution, resolution, resolution]))
 }
 
 /// Server function that loads a .npy file and returns it as JSON-serialisable data.
@@ -164,12 +1012,29 @@ pub async fn run_model(
 pub async fn load_npy(run_id: String) -> Result<NpyData, ServerFnError> {
     use npyz::NpyFile;
 
+    #[cfg(feature = "instrumented")]
+    let _alloc_guard = AllocGuard::new("load_npy");
+
     println!("[load_npy] Called with run_id: {}", run_id);
 
-    // First, try to read the .npy file from disk
+    // First, try to read the .npy file from disk, preferring a memory-mapped
+    // read when the dtype/layout allow it; the mapped view is copied once into
+    // the returned Vec, skipping the intermediate whole-file buffer.
     let path = format!("{run_id}.npy");
     println!("[load_npy] Attempting to read from disk: {}", path);
-    
+
+    if let Ok(view) = load_npy_mmap(&path) {
+        println!(
+            "[load_npy] SUCCESS (mmap): {} f32 values with shape {:?}",
+            view.as_f32_slice().len(),
+            view.shape
+        );
+        return Ok(NpyData {
+            data: view.as_f32_slice().to_vec(),
+            shape: view.shape,
+        });
+    }
+
     let bytes = match std::fs::read(&path) {
         Ok(data) => {
             println!("[load_npy] Successfully read {} bytes from disk", data.len());
@@ -177,29 +1042,44 @@ pub async fn load_npy(run_id: String) -> Result<NpyData, ServerFnError> {
         },
         Err(e) => {
             println!("[load_npy] Failed to read from disk: {}", e);
-            // Fallback: try to fetch from API (if available)
+            // Fallback: try to fetch from API (if available), streaming the body
+            // so we don't buffer a multi-megabyte cosmological array in one shot.
             let api_url = format!("http://localhost:8000/api/simulations/{run_id}/npy");
-            println!("[load_npy] Attempting to fetch from API: {}", api_url);
-            match reqwest::get(&api_url).await {
-                Ok(resp) if resp.status().is_success() => {
-                    println!("[load_npy] API request successful");
-                    resp
+            println!("[load_npy] Attempting to stream-fetch from API: {}", api_url);
+
+            let on_progress = |received: u64, total: Option<u64>| match total {
+                Some(total) if total > 0 => println!(
+                    "[load_npy] download progress: {:.1}% ({}/{} bytes)",
+                    (received as f64 / total as f64) * 100.0,
+                    received,
+                    total
+                ),
+                _ => println!("[load_npy] downloaded {} bytes (total unknown)", received),
+            };
+
+            match fetch_npy_streamed(&api_url, on_progress).await {
+                Ok(npy_data) => {
+                    println!(
+                        "[load_npy] SUCCESS (streamed): {} f32 values with shape {:?}",
+                        npy_data.data.len(),
+                        npy_data.shape
+                    );
+                    return Ok(npy_data);
+                }
+                Err(NpyStreamError::Unsupported(reason)) => {
+                    println!(
+                        "[load_npy] Streaming loader can't handle this file ({}), falling back to buffered fetch",
+                        reason
+                    );
+                    reqwest::get(&api_url)
+                        .await
+                        .map_err(|e| ServerFnError::new(format!("Failed to read {}: {}, and API request failed: {}", path, e, e)))?
                         .bytes()
                         .await
-                        .map_err(|e| {
-                            let err_msg = format!("Failed to read response body: {}", e);
-                            println!("[load_npy] ERROR: {}", err_msg);
-                            ServerFnError::new(err_msg)
-                        })?
+                        .map_err(|e| ServerFnError::new(format!("Failed to read response body: {}", e)))?
                         .to_vec()
-                },
-                Ok(resp) => {
-                    let err_msg = format!("API returned non-success status: {}", resp.status());
-                    println!("[load_npy] ERROR: {}", err_msg);
-                    return Err(ServerFnError::new(err_msg));
-                },
-                Err(e) => {
-                    let err_msg = format!("Failed to read {}: {}, and API request failed: {}", path, e, e);
+                }
+                Err(NpyStreamError::Failed(err_msg)) => {
                     println!("[load_npy] ERROR: {}", err_msg);
                     return Err(ServerFnError::new(err_msg));
                 }
@@ -207,7 +1087,7 @@ pub async fn load_npy(run_id: String) -> Result<NpyData, ServerFnError> {
         }
     };
 
-    // Parse the .npy file
+    // Parse the .npy file (disk path, or the buffered-fallback API path)
     println!("[load_npy] Parsing .npy file from {} bytes", bytes.len());
     let npy = NpyFile::new(&bytes[..])
         .map_err(|e| {
@@ -231,87 +1111,150 @@ pub async fn load_npy(run_id: String) -> Result<NpyData, ServerFnError> {
     Ok(NpyData { data, shape })
 }
 
-/// Server function that generates a random 64x64x64 array with n random floats
-/// between 1 and 1000, and -1 for the rest.
+/// Grids at or above this resolution are written as a sparse coordinate list
+/// (`input_coords.npy` + `input_values.npy`) instead of a dense array, since a
+/// 128³+ grid seeded with a few hundred galaxies is almost entirely void cells.
+#[cfg(feature = "ssr")]
+const SPARSE_GRID_THRESHOLD: u64 = 128;
+
+/// Server function that saves galaxy placements from the client into a fresh
+/// job's input grid.
+///
+/// Each call creates a new `job_id` and writes to `jobs/{job_id}/` instead of
+/// a shared global path, so two browsers placing galaxies at once don't
+/// clobber each other's data (or race the background `run_model` kicked off
+/// from "Place Galaxies"). For `resolution >= SPARSE_GRID_THRESHOLD`, the
+/// input is written as a sparse coordinate list rather than a dense grid.
 ///
 /// # Arguments
-/// * `n` - Number of random float elements (1-1000 range). Defaults to random between 50-500 if None
+/// * `galaxy_json` - JSON object of galaxies, each `[density, x, y, z]`
+/// * `resolution` - Grid resolution per axis; defaults to 64 (the original hardcoded size)
 ///
 /// # Returns
-/// Save galaxy data from JavaScript to user_input.npy
+/// The `job_id` to pass to `run_model` and `poll_job`.
 #[server]
-pub async fn save_galaxy_data(galaxy_json: String) -> Result<(), ServerFnError> {
+pub async fn save_galaxy_data(
+    galaxy_json: String,
+    resolution: Option<u64>,
+) -> Result<JobId, ServerFnError> {
     use serde_json::Value;
 
-    println!("[save_galaxy_data] Received galaxy data JSON");
+    #[cfg(feature = "instrumented")]
+    let _alloc_guard = AllocGuard::new("save_galaxy_data");
+
+    let resolution = resolution.unwrap_or(64);
+    println!(
+        "[save_galaxy_data] Received galaxy data JSON (resolution {}³)",
+        resolution
+    );
 
     // Parse the galaxy JSON
     let galaxy_map: Value = serde_json::from_str(&galaxy_json)
         .map_err(|e| ServerFnError::new(format!("Failed to parse galaxy JSON: {}", e)))?;
 
-    // Create grid filled with -1.0 (64, 64, 64)
-    let mut array_data = vec![-1.0; 64 * 64 * 64];
-
-    // Fill the grid with proper density values
+    // Collect into (coord, density) pairs, rejecting out-of-range coordinates
+    // instead of silently dropping them like the old hardcoded-64 bounds check did.
+    let mut coords: Vec<[u64; 3]> = Vec::new();
+    let mut values: Vec<f32> = Vec::new();
     if let Some(obj) = galaxy_map.as_object() {
         for (_, value) in obj.iter() {
             if let Some(arr) = value.as_array() {
                 if arr.len() >= 4 {
                     // Array format: [density, x, y, z]
                     let density = arr[0].as_f64().unwrap_or(-1.0) as f32;
-                    let x = arr[1].as_u64().unwrap_or(0) as usize;
-                    let y = arr[2].as_u64().unwrap_or(0) as usize;
-                    let z = arr[3].as_u64().unwrap_or(0) as usize;
-
-                    // grid[x, y, z] = density
-                    if x < 64 && y < 64 && z < 64 {
-                        let index = x * 64 * 64 + y * 64 + z;
-                        array_data[index] = density;
+                    let x = arr[1].as_u64().unwrap_or(0);
+                    let y = arr[2].as_u64().unwrap_or(0);
+                    let z = arr[3].as_u64().unwrap_or(0);
+
+                    if x >= resolution || y >= resolution || z >= resolution {
+                        let err_msg = format!(
+                            "Galaxy coordinate ({x}, {y}, {z}) is out of bounds for a {resolution}³ grid"
+                        );
+                        println!("[save_galaxy_data] ERROR: {}", err_msg);
+                        return Err(ServerFnError::new(err_msg));
                     }
+                    coords.push([x, y, z]);
+                    values.push(density);
                 }
             }
         }
     }
 
-    // Write to user_input.npy - save data as binary with NPY header
-    let mut npy_data = Vec::new();
-    
-    // NPY magic number
-    npy_data.extend_from_slice(b"\x93NUMPY");
-    
-    // Version (1, 0)
-    npy_data.push(1);
-    npy_data.push(0);
-    
-    // Header dict as string
-    let header_dict = format!(
-        "{{'descr': '<f4', 'fortran_order': False, 'shape': (64, 64, 64)}}                                                                             "
-    );
-    let header_len = header_dict.len() as u16;
-    npy_data.extend_from_slice(&header_len.to_le_bytes());
-    npy_data.extend_from_slice(header_dict.as_bytes());
-    
-    // Data (f32 in little-endian)
-    for &val in &array_data {
-        npy_data.extend_from_slice(&val.to_le_bytes());
-    }
-    
-    std::fs::write("user_input.npy", &npy_data).map_err(|e| {
-        let err_msg = format!("Failed to save user_input.npy: {}", e);
+    let job_id = new_job_id();
+    let dir = job_dir(&job_id);
+    std::fs::create_dir_all(&dir).map_err(|e| {
+        let err_msg = format!("Failed to create job dir for {}: {}", job_id, e);
         println!("[save_galaxy_data] ERROR: {}", err_msg);
         ServerFnError::new(err_msg)
     })?;
 
-    println!("[save_galaxy_data] SUCCESS: Galaxy data saved to user_input.npy");
-    Ok(())
+    if resolution >= SPARSE_GRID_THRESHOLD {
+        println!(
+            "[save_galaxy_data] Writing sparse COO input: {} nonzero cells in a {}³ grid",
+            coords.len(),
+            resolution
+        );
+        let flat_coords: Vec<u64> = coords.iter().flatten().copied().collect();
+        write_npy(&dir.join("input_coords.npy"), &[coords.len() as u64, 3], &flat_coords).map_err(|e| {
+            let err_msg = format!("Failed to save input_coords.npy for job {}: {}", job_id, e);
+            println!("[save_galaxy_data] ERROR: {}", err_msg);
+            ServerFnError::new(err_msg)
+        })?;
+        write_npy(&dir.join("input_values.npy"), &[values.len() as u64], &values).map_err(|e| {
+            let err_msg = format!("Failed to save input_values.npy for job {}: {}", job_id, e);
+            println!("[save_galaxy_data] ERROR: {}", err_msg);
+            ServerFnError::new(err_msg)
+        })?;
+        // `run_model` needs the grid resolution to densify this COO pair back
+        // into an array; stash it as a 1-element npy alongside them.
+        write_npy(&dir.join("input_resolution.npy"), &[1u64], &[resolution]).map_err(|e| {
+            let err_msg = format!("Failed to save input_resolution.npy for job {}: {}", job_id, e);
+            println!("[save_galaxy_data] ERROR: {}", err_msg);
+            ServerFnError::new(err_msg)
+        })?;
+    } else {
+        // Dense grid filled with the void sentinel -1.0
+        let total = (resolution * resolution * resolution) as usize;
+        let mut array_data = vec![-1.0f32; total];
+        for (coord, &density) in coords.iter().zip(values.iter()) {
+            let [x, y, z] = *coord;
+            let index = (x * resolution * resolution + y * resolution + z) as usize;
+            array_data[index] = density;
+        }
+        write_npy(
+            &dir.join("input.npy"),
+            &[resolution, resolution, resolution],
+            &array_data,
+        )
+        .map_err(|e| {
+            let err_msg = format!("Failed to save input.npy for job {}: {}", job_id, e);
+            println!("[save_galaxy_data] ERROR: {}", err_msg);
+            ServerFnError::new(err_msg)
+        })?;
+    }
+    set_job_status(&job_id, JobStatus::Queued);
+
+    println!("[save_galaxy_data] SUCCESS: Galaxy data saved to job {}", job_id);
+    Ok(job_id)
 }
 
-/// NpyData with shape [64, 64, 64] saved as user_input.npy
+/// Server function that generates a random 64x64x64 array with n random floats
+/// between 1 and 1000, and -1 for the rest, and stashes it as a fresh job's
+/// input grid (`jobs/{job_id}/input.npy`).
+///
+/// # Arguments
+/// * `n` - Number of random float elements (1-1000 range). Defaults to random between 50-500 if None
+///
+/// # Returns
+/// The `job_id` to pass to `run_model` and `poll_job`.
 #[server]
-pub async fn generate_npy_data(n: Option<u64>) -> Result<NpyData, ServerFnError> {
+pub async fn generate_npy_data(n: Option<u64>) -> Result<JobId, ServerFnError> {
     use ndarray::Array3;
     use rand::distributions::Uniform;
 
+    #[cfg(feature = "instrumented")]
+    let _alloc_guard = AllocGuard::new("generate_npy_data");
+
     println!("[generate_npy_data] Called with n: {:?}", n);
 
     // Determine number of random elements
@@ -353,64 +1296,571 @@ pub async fn generate_npy_data(n: Option<u64>) -> Result<NpyData, ServerFnError>
 
     // Flatten to f32 vec
     let data: Vec<f32> = array.into_iter().collect();
-    let shape = vec![64u64, 64u64, 64u64];
 
-    // Write to user_input.npy - save data as binary with NPY header
-    println!("[generate_npy_data] Writing to user_input.npy...");
-    
-    // Create NPY file manually
-    let mut npy_data = Vec::new();
-    
-    // NPY magic number
-    npy_data.extend_from_slice(b"\x93NUMPY");
-    
-    // Version (1, 0)
-    npy_data.push(1);
-    npy_data.push(0);
-    
-    // Header dict as string
-    let header_dict = format!(
-        "{{'descr': '<f4', 'fortran_order': False, 'shape': (64, 64, 64)}}                                                                             "
-    );
-    let header_len = header_dict.len() as u16;
-    npy_data.extend_from_slice(&header_len.to_le_bytes());
-    npy_data.extend_from_slice(header_dict.as_bytes());
-    
-    // Data (f32 in little-endian)
-    for &val in &data {
-        npy_data.extend_from_slice(&val.to_le_bytes());
-    }
-    
-    std::fs::write("user_input.npy", &npy_data).map_err(|e| {
-        let err_msg = format!("Failed to save user_input.npy: {}", e);
+    // Write to jobs/{job_id}/input.npy via the general npyz-backed writer
+    println!("[generate_npy_data] Writing to a fresh job's input.npy...");
+
+    let job_id = new_job_id();
+    let dir = job_dir(&job_id);
+    std::fs::create_dir_all(&dir).map_err(|e| {
+        let err_msg = format!("Failed to create job dir for {}: {}", job_id, e);
+        println!("[generate_npy_data] ERROR: {}", err_msg);
+        ServerFnError::new(err_msg)
+    })?;
+    write_npy(&dir.join("input.npy"), &[64, 64, 64], &data).map_err(|e| {
+        let err_msg = format!("Failed to save input.npy for job {}: {}", job_id, e);
         println!("[generate_npy_data] ERROR: {}", err_msg);
         ServerFnError::new(err_msg)
     })?;
+    set_job_status(&job_id, JobStatus::Queued);
+
+    println!(
+        "[generate_npy_data] SUCCESS: Generated {} elements for job {}",
+        data.len(),
+        job_id
+    );
+    Ok(job_id)
+}
+
+/// Server function backing "Generate Time Series": seeds one 64x64x64 density
+/// grid and evolves it `steps` times via [`evolve_density_grid`], writing each
+/// snapshot to its own job's `input.npy`. Unlike [`generate_npy_data`], the
+/// resulting jobs form a correlated sequence - density peaks grow and spread
+/// frame over frame - so scrubbing through them traces structure formation
+/// instead of lerping between unrelated noise.
+///
+/// # Arguments
+/// * `steps` - Number of snapshots to generate, clamped to 1-20. Defaults to 5.
+/// * `n` - Number of seed density peaks, clamped to 50-500. Defaults to random between 50-500 if None
+///
+/// # Returns
+/// The `job_id`s, in chronological order, to pass to `run_model_sequence`.
+#[server]
+pub async fn generate_npy_sequence(steps: Option<u64>, n: Option<u64>) -> Result<Vec<JobId>, ServerFnError> {
+    use ndarray::Array3;
+    use rand::distributions::Uniform;
+    use rand::seq::SliceRandom;
+    use rand::Rng;
+
+    #[cfg(feature = "instrumented")]
+    let _alloc_guard = AllocGuard::new("generate_npy_sequence");
+
+    let steps = steps.unwrap_or(5).clamp(1, 20);
+    let num_random = if let Some(count) = n {
+        count.min(500).max(50)
+    } else {
+        rand::thread_rng().gen_range(50..501)
+    };
+
+    println!(
+        "[generate_npy_sequence] Seeding {} peaks, evolving {} steps",
+        num_random, steps
+    );
+
+    const SIDE: usize = 64;
+    let total_elements = SIDE * SIDE * SIDE;
+    let mut array: Array3<f32> = Array3::from_elem((SIDE, SIDE, SIDE), -1.0);
+
+    let mut indices: Vec<usize> = (0..total_elements).collect();
+    let mut rng = rand::thread_rng();
+    indices.shuffle(&mut rng);
+    let dist = Uniform::new(1.0, 50.0);
+    for i in 0..(num_random as usize).min(total_elements) {
+        let idx = indices[i];
+        let x = idx / (SIDE * SIDE);
+        let y = (idx / SIDE) % SIDE;
+        let z = idx % SIDE;
+        array[[x, y, z]] = rng.sample(dist);
+    }
+
+    let mut job_ids = Vec::with_capacity(steps as usize);
+    for step in 0..steps {
+        if step > 0 {
+            evolve_density_grid(&mut array);
+        }
+        let data: Vec<f32> = array.iter().copied().collect();
+
+        let job_id = new_job_id();
+        let dir = job_dir(&job_id);
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            let err_msg = format!("Failed to create job dir for {}: {}", job_id, e);
+            println!("[generate_npy_sequence] ERROR: {}", err_msg);
+            ServerFnError::new(err_msg)
+        })?;
+        write_npy(
+            &dir.join("input.npy"),
+            &[SIDE as u64, SIDE as u64, SIDE as u64],
+            &data,
+        )
+        .map_err(|e| {
+            let err_msg = format!("Failed to save input.npy for job {}: {}", job_id, e);
+            println!("[generate_npy_sequence] ERROR: {}", err_msg);
+            ServerFnError::new(err_msg)
+        })?;
+        set_job_status(&job_id, JobStatus::Queued);
+        job_ids.push(job_id);
+    }
+
+    println!(
+        "[generate_npy_sequence] SUCCESS: generated {} evolving snapshots",
+        job_ids.len()
+    );
+    Ok(job_ids)
+}
+
+/// Grows every existing density peak in place and diffuses a fraction of it
+/// into its 6-connected neighbors, turning adjacent void cells non-void over
+/// time. Repeated calls trace out clustering/growth along the grid rather
+/// than independent per-call noise, approximating structure formation.
+#[cfg(feature = "ssr")]
+fn evolve_density_grid(array: &mut ndarray::Array3<f32>) {
+    const GROWTH_FACTOR: f32 = 1.15;
+    const DIFFUSION_FRACTION: f32 = 0.05;
+
+    let previous = array.clone();
+    let shape = previous.dim();
+    for x in 0..shape.0 {
+        for y in 0..shape.1 {
+            for z in 0..shape.2 {
+                let density = previous[[x, y, z]];
+                if density <= 0.0 {
+                    continue;
+                }
+                let grown = (density * GROWTH_FACTOR).min(1000.0);
+                // Add the growth rather than overwrite, so leaks this cell
+                // already received from earlier-visited neighbors are kept.
+                array[[x, y, z]] += grown - density;
+
+                let leaked = grown * DIFFUSION_FRACTION;
+                for (dx, dy, dz) in [(1i64, 0i64, 0i64), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)] {
+                    let (nx, ny, nz) = (x as i64 + dx, y as i64 + dy, z as i64 + dz);
+                    if nx < 0
+                        || ny < 0
+                        || nz < 0
+                        || nx as usize >= shape.0
+                        || ny as usize >= shape.1
+                        || nz as usize >= shape.2
+                    {
+                        continue;
+                    }
+                    let (nx, ny, nz) = (nx as usize, ny as usize, nz as usize);
+                    array[[nx, ny, nz]] = array[[nx, ny, nz]].max(0.0) + leaked;
+                }
+            }
+        }
+    }
+}
+
+/// Density at/below this value is the void sentinel (-1.0 in this grid);
+/// treated as a heavily-penalized-but-not-impassable region for filament tracing.
+#[cfg(feature = "ssr")]
+const FILAMENT_VOID_THRESHOLD: f32 = 0.0;
+
+/// Floor applied to density before inverting it into an edge weight, so a
+/// near-zero (but not void) density doesn't blow up to `f32::INFINITY`.
+#[cfg(feature = "ssr")]
+const FILAMENT_DENSITY_EPS: f32 = 1e-3;
+
+/// Runs Dijkstra from `source` to `target` over a `(nx, ny, nz)` density grid,
+/// 26-connected, preferring high-density ridges. Returns the ordered cell-index
+/// path (inclusive of both ends), or `None` if `target` is unreachable.
+#[cfg(feature = "ssr")]
+fn shortest_density_path(
+    densities: &[f32],
+    (nx, ny, nz): (usize, usize, usize),
+    source: usize,
+    target: usize,
+) -> Option<Vec<usize>> {
+    use ordered_float::OrderedFloat;
+    use std::cmp::Reverse;
+    use std::collections::BinaryHeap;
+
+    let to_xyz = |cell: usize| (cell / (ny * nz), (cell / nz) % ny, cell % nz);
+    let to_cell = |x: usize, y: usize, z: usize| x * ny * nz + y * nz + z;
+
+    let n_cells = nx * ny * nz;
+    let mut dist = vec![f32::INFINITY; n_cells];
+    let mut came_from: Vec<u32> = vec![u32::MAX; n_cells];
+    let mut heap = BinaryHeap::new();
+
+    dist[source] = 0.0;
+    heap.push(Reverse((OrderedFloat(0.0), source)));
+
+    while let Some(Reverse((OrderedFloat(cost), cell))) = heap.pop() {
+        if cost > dist[cell] {
+            continue; // stale entry superseded by a cheaper path already popped
+        }
+        if cell == target {
+            break;
+        }
+
+        let (x, y, z) = to_xyz(cell);
+        for dx in -1i64..=1 {
+            for dy in -1i64..=1 {
+                for dz in -1i64..=1 {
+                    if dx == 0 && dy == 0 && dz == 0 {
+                        continue;
+                    }
+                    let (nx_, ny_, nz_) = (x as i64 + dx, y as i64 + dy, z as i64 + dz);
+                    if nx_ < 0 || ny_ < 0 || nz_ < 0 || nx_ >= nx as i64 || ny_ >= ny as i64 || nz_ >= nz as i64 {
+                        continue;
+                    }
+                    let neighbor = to_cell(nx_ as usize, ny_ as usize, nz_ as usize);
+
+                    let step_distance =
+                        ((dx * dx + dy * dy + dz * dz) as f32).sqrt();
+                    let raw_density = densities[neighbor];
+                    let effective_density = if raw_density <= FILAMENT_VOID_THRESHOLD {
+                        FILAMENT_DENSITY_EPS
+                    } else {
+                        raw_density.max(FILAMENT_DENSITY_EPS)
+                    };
+                    let weight = step_distance * (1.0 / effective_density);
+
+                    let new_cost = cost + weight;
+                    if new_cost < dist[neighbor] {
+                        dist[neighbor] = new_cost;
+                        came_from[neighbor] = cell as u32;
+                        heap.push(Reverse((OrderedFloat(new_cost), neighbor)));
+                    }
+                }
+            }
+        }
+    }
+
+    if dist[target].is_infinite() {
+        return None;
+    }
+
+    let mut path = vec![target];
+    let mut current = target;
+    while current != source {
+        current = came_from[current] as usize;
+        path.push(current);
+    }
+    path.reverse();
+    Some(path)
+}
+
+/// Server function that traces dark-matter filaments connecting `seed_cells`
+/// (e.g. galaxy positions) through high-density regions of a job's output
+/// density grid, for highlighting via `set_opacities_from_densities`.
+///
+/// Runs a Dijkstra search between each consecutive pair of seeds, where edge
+/// weight is `euclidean_step_distance / density`, so the search prefers dense
+/// ridges over the void. Void cells are heavily penalized but never
+/// impassable, so every in-bounds seed pair gets a path. Returns one flat
+/// cell-index path per consecutive seed pair; a pair for which no path is
+/// found gets an empty path rather than an error.
+///
+/// # Arguments
+/// * `job_id` - Job whose `output.npy` (from `run_model`) to search over
+/// * `seed_cells` - Ordered `[x, y, z]` cell coordinates to connect, in sequence
+#[server]
+pub async fn trace_filaments(
+    job_id: JobId,
+    seed_cells: Vec<[usize; 3]>,
+) -> Result<Vec<Vec<usize>>, ServerFnError> {
+    use npyz::NpyFile;
+
+    println!("[trace_filaments] Called for job {} with {} seeds", job_id, seed_cells.len());
+
+    let output_path = job_dir(&job_id).join("output.npy");
+    let (densities, shape) = if let Ok(view) = load_npy_mmap(output_path.to_str().unwrap_or_default()) {
+        (view.as_f32_slice().to_vec(), view.shape)
+    } else {
+        let bytes = std::fs::read(&output_path).map_err(|e| {
+            ServerFnError::new(format!("Failed to read output.npy for job {}: {}", job_id, e))
+        })?;
+        let npy = NpyFile::new(&bytes[..])
+            .map_err(|e| ServerFnError::new(format!("Failed to parse output.npy for job {}: {}", job_id, e)))?;
+        let shape = npy.shape().to_vec();
+        let data = npy
+            .into_vec::<f32>()
+            .map_err(|e| ServerFnError::new(format!("Failed to read output.npy data for job {}: {}", job_id, e)))?;
+        (data, shape)
+    };
+
+    if shape.len() != 3 {
+        return Err(ServerFnError::new(format!(
+            "Expected a 3-D density grid, got shape {:?}",
+            shape
+        )));
+    }
+    let (nx, ny, nz) = (shape[0] as usize, shape[1] as usize, shape[2] as usize);
+    let to_cell = |c: [usize; 3]| c[0] * ny * nz + c[1] * nz + c[2];
+
+    for seed in &seed_cells {
+        if seed[0] >= nx || seed[1] >= ny || seed[2] >= nz {
+            return Err(ServerFnError::new(format!(
+                "Seed cell {:?} is out of bounds for a {}x{}x{} grid",
+                seed, nx, ny, nz
+            )));
+        }
+    }
+
+    let mut paths = Vec::new();
+    for pair in seed_cells.windows(2) {
+        let source = to_cell(pair[0]);
+        let target = to_cell(pair[1]);
+        match shortest_density_path(&densities, (nx, ny, nz), source, target) {
+            Some(path) => {
+                println!("[trace_filaments] Found path of {} cells from {:?} to {:?}", path.len(), pair[0], pair[1]);
+                paths.push(path);
+            }
+            None => {
+                println!("[trace_filaments] No path found from {:?} to {:?} (target unreachable)", pair[0], pair[1]);
+                paths.push(Vec::new());
+            }
+        }
+    }
+
+    Ok(paths)
+}
+
+/// Server function that reads back a previously-computed job's `output.npy`
+/// without re-running inference. Used by temporal playback to re-fetch a
+/// frame that was evicted from the client-side `FrameCache`.
+#[server]
+pub async fn get_job_output(job_id: JobId) -> Result<NpyData, ServerFnError> {
+    use npyz::NpyFile;
+
+    let output_path = job_dir(&job_id).join("output.npy");
+    if let Ok(view) = load_npy_mmap(output_path.to_str().unwrap_or_default()) {
+        return Ok(NpyData {
+            data: view.as_f32_slice().to_vec(),
+            shape: view.shape,
+        });
+    }
+
+    let bytes = std::fs::read(&output_path).map_err(|e| {
+        ServerFnError::new(format!("Failed to read output.npy for job {}: {}", job_id, e))
+    })?;
+    let npy = NpyFile::new(&bytes[..])
+        .map_err(|e| ServerFnError::new(format!("Failed to parse output.npy for job {}: {}", job_id, e)))?;
+    let shape = npy.shape().to_vec();
+    let data = npy
+        .into_vec::<f32>()
+        .map_err(|e| ServerFnError::new(format!("Failed to read output.npy data for job {}: {}", job_id, e)))?;
+    Ok(NpyData { data, shape })
+}
+
+/// Server function that runs `run_model` across an ordered sequence of
+/// snapshot jobs (one per redshift/time step), for `DarkMatterScene`'s
+/// temporal playback. Each job's output is persisted to its own
+/// `jobs/{job_id}/output.npy` as a side effect of `run_model`, so later
+/// re-fetches (via `get_job_output`) don't need to re-run inference.
+#[server]
+pub async fn run_model_sequence(
+    job_ids: Vec<JobId>,
+    model_path: String,
+) -> Result<Vec<NpyData>, ServerFnError> {
+    println!("[run_model_sequence] Running {} snapshots through {}", job_ids.len(), model_path);
+    let mut outputs = Vec::with_capacity(job_ids.len());
+    for (i, job_id) in job_ids.into_iter().enumerate() {
+        println!("[run_model_sequence] STEP {}/{}: job {}", i + 1, outputs.capacity(), job_id);
+        let output = run_model(job_id, model_path.clone()).await?;
+        outputs.push(output);
+    }
+    Ok(outputs)
+}
+
+#[cfg(not(feature = "ssr"))]
+use wasm_bindgen::prelude::*;
+
+#[cfg(not(feature = "ssr"))]
+#[wasm_bindgen(module = "/three.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = initScene)]
+    fn init_scene(canvas_id: &str, container_id: &str);
+
+    #[wasm_bindgen(js_name = listenForKey)]
+    fn listen_for_key(key: &str, callback: &Closure<dyn Fn()>);
+
+    #[wasm_bindgen(js_name = setOpacitiesFromDensities)]
+    fn set_opacities_from_densities(array: &[f32]);
+
+    #[wasm_bindgen(js_name = setOpacitiesForCanvas)]
+    fn set_opacities_for_canvas(canvas_id: &str, array: &[f32]);
+
+    #[wasm_bindgen(js_name = generateGalaxies)]
+    fn generate_galaxies(count: u32);
+
+    #[wasm_bindgen(js_name = getGalaxies)]
+    fn get_galaxies() -> String;
+
+    #[wasm_bindgen(js_name = getCameraPose)]
+    fn get_camera_pose() -> String;
+
+    #[wasm_bindgen(js_name = setCameraPose)]
+    fn set_camera_pose(pose_json: &str);
+}
+
+/// WebGPU inference bridge: runs the UNet directly in the browser, skipping
+/// the `run_model` server round-trip entirely. Weights are 8-bit quantized
+/// with a scale/zero-point stored per tensor; `runUnetWebgpu` dequantizes
+/// them on upload (or fuses dequantization into the matmul) when the device
+/// supports int8 compute, and otherwise loads the full-precision weights.
+#[cfg(not(feature = "ssr"))]
+#[wasm_bindgen(module = "/webgpu_infer.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = isWebgpuAvailable)]
+    fn is_webgpu_available() -> bool;
+
+    #[wasm_bindgen(js_name = supportsInt8Compute)]
+    fn supports_int8_compute() -> bool;
 
-    println!("[generate_npy_data] SUCCESS: Generated and saved user_input.npy with {} elements", data.len());
-    Ok(NpyData { data, shape })
+    #[wasm_bindgen(js_name = runUnetWebgpu)]
+    fn run_unet_webgpu(input_json: &str, weights_url: &str, quantized: bool) -> js_sys::Promise;
 }
 
 #[cfg(not(feature = "ssr"))]
-use wasm_bindgen::prelude::*;
+const MODEL_WEIGHTS_INT8_URL: &str = "/assets/model_final_int8.bin";
+#[cfg(not(feature = "ssr"))]
+const MODEL_WEIGHTS_F32_URL: &str = "/assets/model_final_f32.bin";
 
+/// Builds the same dense `resolution`³ grid `save_galaxy_data` writes to
+/// `input.npy`, but entirely client-side so WebGPU inference never has to
+/// ship the galaxy placements to the server. Mirrors that function's dense
+/// branch; out-of-range coordinates are rejected rather than dropped.
 #[cfg(not(feature = "ssr"))]
-#[wasm_bindgen(module = "/three.js")]
-extern "C" {
-    #[wasm_bindgen(js_name = initScene)]
-    fn init_scene(canvas_id: &str, container_id: &str);
+fn build_input_grid_client(galaxy_json: &str, resolution: u64) -> Result<NpyData, String> {
+    let galaxy_map: serde_json::Value = serde_json::from_str(galaxy_json).map_err(|e| e.to_string())?;
+    let total = (resolution * resolution * resolution) as usize;
+    let mut data = vec![-1.0f32; total];
+    if let Some(obj) = galaxy_map.as_object() {
+        for value in obj.values() {
+            let Some(arr) = value.as_array() else { continue };
+            if arr.len() < 4 {
+                continue;
+            }
+            let density = arr[0].as_f64().unwrap_or(-1.0) as f32;
+            let x = arr[1].as_u64().unwrap_or(0);
+            let y = arr[2].as_u64().unwrap_or(0);
+            let z = arr[3].as_u64().unwrap_or(0);
+            if x >= resolution || y >= resolution || z >= resolution {
+                return Err(format!("Galaxy coordinate ({x}, {y}, {z}) is out of bounds for a {resolution}³ grid"));
+            }
+            let index = (x * resolution * resolution + y * resolution + z) as usize;
+            data[index] = density;
+        }
+    }
+    Ok(NpyData { data, shape: vec![resolution, resolution, resolution] })
+}
 
-    #[wasm_bindgen(js_name = listenForKey)]
-    fn listen_for_key(key: &str, callback: &Closure<dyn Fn()>);
+/// Runs inference in-browser via WebGPU instead of the `run_model` server
+/// round-trip. Falls back to full-precision weights when the device lacks
+/// int8 compute support, and returns an error (so the caller can fall back
+/// to the server backend) when WebGPU itself isn't available at all.
+#[cfg(not(feature = "ssr"))]
+async fn run_model_webgpu(input: &NpyData) -> Result<NpyData, String> {
+    if !is_webgpu_available() {
+        return Err("WebGPU is not available in this browser".to_string());
+    }
+    let quantized = supports_int8_compute();
+    let weights_url = if quantized { MODEL_WEIGHTS_INT8_URL } else { MODEL_WEIGHTS_F32_URL };
+
+    let input_json = serde_json::to_string(input).map_err(|e| e.to_string())?;
+    let promise = run_unet_webgpu(&input_json, weights_url, quantized);
+    let result = wasm_bindgen_futures::JsFuture::from(promise).await.map_err(|e| format!("{e:?}"))?;
+    let result_str = result.as_string().ok_or_else(|| "unexpected WebGPU inference result type".to_string())?;
+    serde_json::from_str::<NpyData>(&result_str).map_err(|e| e.to_string())
+}
 
-    #[wasm_bindgen(js_name = setOpacitiesFromDensities)]
-    fn set_opacities_from_densities(array: &[f32]);
+/// Polls `poll_job` every 250ms and writes a human-readable progress string
+/// into `status`, stopping once the job reaches a terminal state.
+#[cfg(not(feature = "ssr"))]
+async fn poll_job_until_terminal(job_id: JobId, status: RwSignal<String>) {
+    loop {
+        match poll_job(job_id.clone()).await {
+            Ok(JobStatus::Queued) => status.set("Model queued...".to_string()),
+            Ok(JobStatus::Running) => status.set("Model running...".to_string()),
+            Ok(JobStatus::Done { shape }) => {
+                status.set(format!("Model complete! (shape {:?})", shape));
+                return;
+            }
+            Ok(JobStatus::Failed { error }) => {
+                status.set(format!("Error: {}", error));
+                return;
+            }
+            Err(e) => {
+                eprintln!("[HomePage] poll_job error: {:?}", e);
+                return;
+            }
+        }
+        gloo_timers::future::TimeoutFuture::new(250).await;
+    }
+}
 
-    #[wasm_bindgen(js_name = generateGalaxies)]
-    fn generate_galaxies(count: u32);
+/// Gets frame `step`'s decoded output, fetching it from the server via
+/// `get_job_output` on a `FrameCache` miss (e.g. after eviction) and caching
+/// the result back.
+#[cfg(not(feature = "ssr"))]
+async fn ensure_frame(step: usize, job_ids: &[JobId], cache: RwSignal<FrameCache>) -> Option<NpyData> {
+    if let Some(data) = cache.try_update(|c| c.get(step)).flatten() {
+        return Some(data);
+    }
+    let job_id = job_ids.get(step)?.clone();
+    match get_job_output(job_id).await {
+        Ok(data) => {
+            cache.update(|c| c.insert(step, data.clone()));
+            Some(data)
+        }
+        Err(e) => {
+            eprintln!("[temporal] Failed to fetch frame {}: {:?}", step, e);
+            None
+        }
+    }
+}
 
-    #[wasm_bindgen(js_name = getGalaxies)]
-    fn get_galaxies() -> String;
+/// Renders the scene for a fractional `playhead` position by linearly
+/// interpolating between the two bracketing frames.
+#[cfg(not(feature = "ssr"))]
+async fn render_playhead(playhead_value: f64, job_ids: Vec<JobId>, cache: RwSignal<FrameCache>) {
+    if job_ids.is_empty() {
+        return;
+    }
+    let last = job_ids.len() - 1;
+    let lo = (playhead_value.floor() as usize).min(last);
+    let hi = (lo + 1).min(last);
+    let t = (playhead_value - lo as f64) as f32;
+
+    let frame_lo = ensure_frame(lo, &job_ids, cache).await;
+    let frame_hi = if hi != lo {
+        ensure_frame(hi, &job_ids, cache).await
+    } else {
+        frame_lo.clone()
+    };
+
+    if let (Some(a), Some(b)) = (frame_lo, frame_hi) {
+        set_opacities_from_densities(&lerp_densities(&a.data, &b.data, t));
+    }
+}
+
+/// Advances `playhead` on a fixed tick while `playing` stays true, wrapping
+/// back to the start at the end of the sequence.
+#[cfg(not(feature = "ssr"))]
+async fn playback_loop(
+    temporal_job_ids: RwSignal<Vec<JobId>>,
+    frame_cache: RwSignal<FrameCache>,
+    playhead: RwSignal<f64>,
+    playing: RwSignal<bool>,
+) {
+    const STEP_PER_TICK: f64 = 0.04;
+    while playing.get_untracked() {
+        let job_ids = temporal_job_ids.get_untracked();
+        if job_ids.len() < 2 {
+            break;
+        }
+        let last = (job_ids.len() - 1) as f64;
+        let mut next = playhead.get_untracked() + STEP_PER_TICK;
+        if next > last {
+            next = 0.0;
+        }
+        playhead.set(next);
+        render_playhead(next, job_ids, frame_cache).await;
+        gloo_timers::future::TimeoutFuture::new(100).await;
+    }
 }
 
 #[component]
@@ -439,25 +1889,59 @@ fn HomePage() -> impl IntoView {
 
     // NEW: About overlay open/close
     let about_open = RwSignal::new(false);
-    
+
+    // Methodology Q&A assistant overlay open/close
+    let chat_open = RwSignal::new(false);
+
     // Galaxy count input state
     let galaxy_count = RwSignal::new("".to_string());
     
     // Model running state
     let model_running = RwSignal::new(false);
     let model_status = RwSignal::new("".to_string());
-    
+
+    // Job backing the most recent "Place Galaxies" click, polled via `poll_job`
+    // so the UI can show real progress instead of a static string.
+    let current_job_id: RwSignal<Option<JobId>> = RwSignal::new(None);
+
     // Cached precomputed model output (for sneaky background processing)
     let cached_model_output: RwSignal<Option<NpyData>> = RwSignal::new(None);
 
+    // Which backend "Run Model" uses: the server round-trip, or in-browser WebGPU.
+    let inference_backend = RwSignal::new(InferenceBackend::default());
+
+    // Model comparison: two registry picks, how to render them, and the
+    // outputs once both have run so split-view/diff can re-render on toggle.
+    let registry = model_registry();
+    let model_a_id = RwSignal::new(registry[0].id.clone());
+    let model_b_id = RwSignal::new(registry.get(1).map(|m| m.id.clone()).unwrap_or_else(|| registry[0].id.clone()));
+    let comparison_mode = RwSignal::new(false);
+    let comparison_render_mode = RwSignal::new(ComparisonRenderMode::default());
+    let model_a_output: RwSignal<Option<NpyData>> = RwSignal::new(None);
+    let model_b_output: RwSignal<Option<NpyData>> = RwSignal::new(None);
+
+    // Temporal playback: one job per redshift/time step, the frames decoded
+    // from their outputs so far, and where the scrubber currently sits.
+    let temporal_job_ids: RwSignal<Vec<JobId>> = RwSignal::new(Vec::new());
+    let frame_cache: RwSignal<FrameCache> = RwSignal::new(FrameCache::default());
+    let playhead = RwSignal::new(0.0f64);
+    let playing = RwSignal::new(false);
+    let temporal_status = RwSignal::new("".to_string());
+
+    // Export/Import of the full scene document (density grid, opacities,
+    // camera pose and settings) as JSON text.
+    let scene_export_text = RwSignal::new(String::new());
+    let scene_import_text = RwSignal::new(String::new());
+    let scene_status = RwSignal::new(String::new());
+
     #[cfg(not(feature = "ssr"))]
     {
         // Generate random NPY on page load
         Effect::new(move |_| {
             spawn_local(async {
                 match generate_npy_data(None).await {
-                    Ok(data) => {
-                        println!("[HomePage] Generated NPY data with shape: {:?}", data.shape);
+                    Ok(job_id) => {
+                        println!("[HomePage] Generated NPY data for job: {}", job_id);
                     }
                     Err(e) => {
                         eprintln!("[HomePage] Error generating NPY: {:?}", e);
@@ -486,10 +1970,29 @@ fn HomePage() -> impl IntoView {
         });
         listen_for_key("i", &about_toggle);
         about_toggle.forget();
+
+        // C toggles the methodology Q&A assistant
+        let chat_toggle = Closure::new(move || {
+            chat_open.update(|v| *v = !*v);
+        });
+        listen_for_key("c", &chat_toggle);
+        chat_toggle.forget();
     }
 
+    let split_view_active = move || {
+        comparison_mode.get()
+            && comparison_render_mode.get() == ComparisonRenderMode::SplitView
+            && model_a_output.get().is_some()
+            && model_b_output.get().is_some()
+    };
+
     view! {
-        <DarkMatterScene/>
+        <Show when=split_view_active fallback=move || view! { <DarkMatterScene/> }>
+            <div class="split-view">
+                <DarkMatterScene canvas_id="scene-canvas-a".to_string() container_id="scene-container-a".to_string()/>
+                <DarkMatterScene canvas_id="scene-canvas-b".to_string() container_id="scene-container-b".to_string()/>
+            </div>
+        </Show>
 
           <audio
                 autoplay=true
@@ -539,8 +2042,8 @@ fn HomePage() -> impl IntoView {
                                 }
                                 spawn_local(async move {
                                     match generate_npy_data(Some(count)).await {
-                                        Ok(data) => {
-                                            println!("[HomePage] Generated NPY data with {} elements", data.data.len());
+                                        Ok(job_id) => {
+                                            println!("[HomePage] Generated NPY data for job: {}", job_id);
                                         }
                                         Err(e) => {
                                             eprintln!("[HomePage] Error generating NPY: {:?}", e);
@@ -552,6 +2055,141 @@ fn HomePage() -> impl IntoView {
                     />
                     <p class="input-note">"Please select a number between 50 and 500"</p>
                 </div>
+                <div class="inference-backend-select">
+                    <label for="inference-backend">"Inference backend"</label>
+                    <select
+                        id="inference-backend"
+                        on:change=move |ev| {
+                            let backend = match event_target_value(&ev).as_str() {
+                                "webgpu" => InferenceBackend::WebGpu,
+                                _ => InferenceBackend::Server,
+                            };
+                            inference_backend.set(backend);
+                        }
+                    >
+                        <option value="server">"Server"</option>
+                        <option value="webgpu">"WebGPU (in-browser)"</option>
+                    </select>
+                </div>
+                <div class="model-comparison">
+                    <h2>"Compare Models"</h2>
+                    <label>
+                        <input
+                            type="checkbox"
+                            prop:checked=comparison_mode
+                            on:change=move |ev| comparison_mode.set(event_target_checked(&ev))
+                        />
+                        " Comparison mode"
+                    </label>
+                    <select
+                        class="model-a-select"
+                        disabled=move || !comparison_mode.get()
+                        on:change=move |ev| model_a_id.set(event_target_value(&ev))
+                    >
+                        <For each=model_registry key=|m| m.id.clone() let:m>
+                            <option value=m.id.clone() selected=move || model_a_id.get() == m.id>
+                                {m.display_name.clone()}
+                            </option>
+                        </For>
+                    </select>
+                    <select
+                        class="model-b-select"
+                        disabled=move || !comparison_mode.get()
+                        on:change=move |ev| model_b_id.set(event_target_value(&ev))
+                    >
+                        <For each=model_registry key=|m| m.id.clone() let:m>
+                            <option value=m.id.clone() selected=move || model_b_id.get() == m.id>
+                                {m.display_name.clone()}
+                            </option>
+                        </For>
+                    </select>
+                    <select
+                        class="comparison-render-mode-select"
+                        disabled=move || !comparison_mode.get()
+                        on:change=move |ev| {
+                            let mode = match event_target_value(&ev).as_str() {
+                                "diff" => ComparisonRenderMode::Diff,
+                                _ => ComparisonRenderMode::SplitView,
+                            };
+                            comparison_render_mode.set(mode);
+                        }
+                    >
+                        <option value="split">"Split view"</option>
+                        <option value="diff">"Difference field"</option>
+                    </select>
+                    <button
+                        class="compare-models-btn"
+                        disabled=move || !comparison_mode.get() || model_running.get()
+                        on:click=move |_| {
+                            let Some(job_id) = current_job_id.get() else {
+                                model_status.set("Place galaxies first.".to_string());
+                                return;
+                            };
+                            let registry = model_registry();
+                            let Some(model_a) = registry.iter().find(|m| m.id == model_a_id.get()).cloned() else { return };
+                            let Some(model_b) = registry.iter().find(|m| m.id == model_b_id.get()).cloned() else { return };
+
+                            model_running.set(true);
+                            model_status.set("Comparing models...".to_string());
+                            spawn_local(async move {
+                                #[cfg(not(feature = "ssr"))]
+                                let t0 = js_sys::Date::now();
+
+                                let result_a = run_model(job_id.clone(), model_a.path.clone()).await;
+                                #[cfg(not(feature = "ssr"))]
+                                let elapsed_a_ms = js_sys::Date::now() - t0;
+
+                                #[cfg(not(feature = "ssr"))]
+                                let t1 = js_sys::Date::now();
+                                let result_b = run_model(job_id, model_b.path.clone()).await;
+                                #[cfg(not(feature = "ssr"))]
+                                let elapsed_b_ms = js_sys::Date::now() - t1;
+
+                                match (result_a, result_b) {
+                                    (Ok(output_a), Ok(output_b)) => {
+                                        #[cfg(not(feature = "ssr"))]
+                                        {
+                                            model_status.set(format!(
+                                                "{}: {:.0}ms  |  {}: {:.0}ms",
+                                                model_a.display_name, elapsed_a_ms, model_b.display_name, elapsed_b_ms
+                                            ));
+                                            match comparison_render_mode.get() {
+                                                ComparisonRenderMode::SplitView => {
+                                                    // Setting these mounts the split-view canvases (see
+                                                    // `split_view_active`); wait a tick so they - and the
+                                                    // `init_scene` call their mount effect triggers - exist
+                                                    // in the DOM before we paint them.
+                                                    model_a_output.set(Some(output_a.clone()));
+                                                    model_b_output.set(Some(output_b.clone()));
+                                                    gloo_timers::future::TimeoutFuture::new(0).await;
+                                                    set_opacities_for_canvas("scene-canvas-a", &output_a.data);
+                                                    set_opacities_for_canvas("scene-canvas-b", &output_b.data);
+                                                }
+                                                ComparisonRenderMode::Diff => {
+                                                    let diff = diff_densities(&output_a.data, &output_b.data);
+                                                    set_opacities_from_densities(&diff);
+                                                    model_a_output.set(Some(output_a));
+                                                    model_b_output.set(Some(output_b));
+                                                }
+                                            }
+                                        }
+                                        #[cfg(feature = "ssr")]
+                                        {
+                                            model_a_output.set(Some(output_a));
+                                            model_b_output.set(Some(output_b));
+                                        }
+                                    }
+                                    (Err(e), _) | (_, Err(e)) => {
+                                        model_status.set(format!("Comparison failed: {:?}", e));
+                                    }
+                                }
+                                model_running.set(false);
+                            });
+                        }
+                    >
+                        "Compare Models"
+                    </button>
+                </div>
                 <div class="button-group">
                     <button
                         class="submit-galaxy-btn"
@@ -561,12 +2199,31 @@ fn HomePage() -> impl IntoView {
                             #[cfg(not(feature = "ssr"))]
                             {
                                 let galaxy_json = get_galaxies();
-                                
-                                // Save galaxy data to server (creates user_input.npy)
+
+                                // Save galaxy data to a fresh job (jobs/{job_id}/input.npy)
                                 spawn_local(async move {
-                                    match save_galaxy_data(galaxy_json).await {
-                                        Ok(_) => {
-                                            println!("[HomePage] Galaxy data saved successfully");
+                                    match save_galaxy_data(galaxy_json, None).await {
+                                        Ok(job_id) => {
+                                            println!("[HomePage] Galaxy data saved to job {}", job_id);
+                                            current_job_id.set(Some(job_id.clone()));
+
+                                            // Sneakily start model inference in the background (no UI
+                                            // updates) - but only on the server backend, the only one
+                                            // for which "Run Model" reads this cache.
+                                            if inference_backend.get_untracked() == InferenceBackend::Server {
+                                                spawn_local(async move {
+                                                    match run_model(job_id, "model_final.keras".to_string()).await {
+                                                        Ok(output_data) => {
+                                                            println!("[HomePage] Background model inference complete. Output shape: {:?}", output_data.shape);
+                                                            // Cache the result but don't update visualization yet
+                                                            cached_model_output.set(Some(output_data));
+                                                        }
+                                                        Err(e) => {
+                                                            eprintln!("[HomePage] Background model error: {:?}", e);
+                                                        }
+                                                    }
+                                                });
+                                            }
                                         }
                                         Err(e) => {
                                             eprintln!("[HomePage] Error saving galaxy data: {:?}", e);
@@ -574,24 +2231,6 @@ fn HomePage() -> impl IntoView {
                                     }
                                 });
                             }
-                            
-                            // Sneakily start model inference in the background (no UI updates)
-                            spawn_local(async move {
-                                match run_model(
-                                    "user_input.npy".to_string(),
-                                    "model_final.keras".to_string(),
-                                    None
-                                ).await {
-                                    Ok(output_data) => {
-                                        println!("[HomePage] Background model inference complete. Output shape: {:?}", output_data.shape);
-                                        // Cache the result but don't update visualization yet
-                                        cached_model_output.set(Some(output_data));
-                                    }
-                                    Err(e) => {
-                                        eprintln!("[HomePage] Background model error: {:?}", e);
-                                    }
-                                }
-                            });
                         }
                     >
                         "Place Galaxies"
@@ -601,10 +2240,67 @@ fn HomePage() -> impl IntoView {
                     on:click=move |_| {
                          let model_running_clone = model_running.clone();
                          let model_status_clone = model_status.clone();
-                         
-                         // Check if we have cached results from background processing
-                         if let Some(_cached_output_data) = cached_model_output.get() {
-                             // Results already computed in background, apply them immediately
+
+                         // Check the selected backend before the cache: the cache is usually
+                         // filled by the background server run from "Place Galaxies", so
+                         // honoring it first would silently skip WebGPU every time it's selected.
+                         if inference_backend.get() == InferenceBackend::WebGpu {
+                             // In-browser WebGPU backend: build the input grid locally and
+                             // run inference in the browser; the server is used only as a
+                             // fallback when WebGPU inference fails.
+                             model_status_clone.set("Running on WebGPU...".to_string());
+                             spawn_local(async move {
+                                 model_running_clone.set(true);
+
+                                 #[cfg(not(feature = "ssr"))]
+                                 {
+                                     let galaxy_json = get_galaxies();
+                                     match build_input_grid_client(&galaxy_json, 64) {
+                                         Ok(input) => match run_model_webgpu(&input).await {
+                                             Ok(output_data) => {
+                                                 println!("[HomePage] WebGPU inference complete. Output shape: {:?}", output_data.shape);
+                                                 set_opacities_from_densities(&output_data.data);
+                                                 model_status_clone.set("Model complete! (WebGPU)".to_string());
+                                                 cached_model_output.set(Some(output_data));
+                                             }
+                                             Err(e) => {
+                                                 eprintln!("[HomePage] WebGPU model error: {}", e);
+                                                 match current_job_id.get() {
+                                                     Some(job_id) => {
+                                                         model_status_clone.set(format!(
+                                                             "WebGPU inference failed ({e}), falling back to server..."
+                                                         ));
+                                                         match run_model(job_id, "model_final.keras".to_string()).await {
+                                                             Ok(output_data) => {
+                                                                 set_opacities_from_densities(&output_data.data);
+                                                                 model_status_clone.set("Model complete! (server fallback)".to_string());
+                                                                 cached_model_output.set(Some(output_data));
+                                                             }
+                                                             Err(server_err) => {
+                                                                 model_status_clone.set(format!(
+                                                                     "WebGPU inference failed ({e}), and server fallback also failed: {server_err:?}"
+                                                                 ));
+                                                             }
+                                                         }
+                                                     }
+                                                     None => {
+                                                         model_status_clone.set(format!(
+                                                             "WebGPU inference failed ({e}); place galaxies to retry via the server."
+                                                         ));
+                                                     }
+                                                 }
+                                             }
+                                         },
+                                         Err(e) => model_status_clone.set(format!("Error: {e}")),
+                                     }
+                                 }
+
+                                 model_running_clone.set(false);
+                             });
+                         } else if let Some(_cached_output_data) = cached_model_output.get() {
+                             // Server backend, and a result is already cached (from the
+                             // background run "Place Galaxies" starts, an earlier WebGPU run,
+                             // or an imported scene) - apply it immediately.
                              println!("[HomePage] Using cached model inference results");
                              #[cfg(not(feature = "ssr"))]
                              {
@@ -612,38 +2308,37 @@ fn HomePage() -> impl IntoView {
                                  set_opacities_from_densities(&cached_output_data.data);
                              }
                              model_status_clone.set("Model complete!".to_string());
-                         } else {
-                             // No cached results yet, show loading message
-                             model_status_clone.set("Model loading... This may take a while".to_string());
-                             
-                             // No cached results, run the model now
+                         } else if let Some(job_id) = current_job_id.get() {
+                             // No cached results yet: kick off inference for the current job
+                             // and poll it so the status line reflects real progress.
+                             model_status_clone.set("Model queued...".to_string());
+
+                             #[cfg(not(feature = "ssr"))]
+                             spawn_local(poll_job_until_terminal(job_id.clone(), model_status_clone));
+
                              spawn_local(async move {
                                  model_running_clone.set(true);
-                                 
-                                 match run_model(
-                                     "user_input.npy".to_string(),
-                                     "model_final.keras".to_string(),
-                                     None
-                                 ).await {
+
+                                 match run_model(job_id, "model_final.keras".to_string()).await {
                                      Ok(output_data) => {
                                          println!("[HomePage] Model inference complete. Output shape: {:?}", output_data.shape);
-                                         
+
                                          // Update visualization with output data
                                          #[cfg(not(feature = "ssr"))]
                                          {
                                              set_opacities_from_densities(&output_data.data);
                                          }
-                                         
-                                         model_status_clone.set("Model complete!".to_string());
                                      }
                                      Err(e) => {
                                          model_status_clone.set(format!("Error: {:?}", e));
                                          eprintln!("[HomePage] Model error: {:?}", e);
                                      }
                                  }
-                                 
+
                                  model_running_clone.set(false);
                              });
+                         } else {
+                             model_status_clone.set("Place galaxies first.".to_string());
                          }
                      }
                     disabled=model_running
@@ -652,6 +2347,167 @@ fn HomePage() -> impl IntoView {
                     </button>
                 </div>
                 <p class="model-status">{move || model_status.get()}</p>
+
+                <div class="scene-io">
+                    <h2>"Scene"</h2>
+                    <button
+                        class="export-scene-btn"
+                        on:click=move |_| {
+                            let Some(output) = cached_model_output.get() else {
+                                scene_status.set("Run the model first.".to_string());
+                                return;
+                            };
+
+                            #[cfg(not(feature = "ssr"))]
+                            {
+                                let camera = serde_json::from_str::<CameraPose>(&get_camera_pose()).ok();
+                                let opacities = densities_to_opacities(&output.data);
+                                match encode_npy_base64(&output.shape, &output.data) {
+                                    Ok(density_npy_base64) => {
+                                        let doc = SceneDocument {
+                                            format_version: SCENE_FORMAT_VERSION,
+                                            density_npy_base64,
+                                            shape: output.shape.clone(),
+                                            opacities,
+                                            camera,
+                                            settings: SceneSettings {
+                                                galaxy_count: galaxy_count.get(),
+                                            },
+                                        };
+                                        match serde_json::to_string_pretty(&doc) {
+                                            Ok(json) => {
+                                                scene_export_text.set(json);
+                                                scene_status.set("Scene exported below.".to_string());
+                                            }
+                                            Err(e) => scene_status.set(format!("Export failed: {e}")),
+                                        }
+                                    }
+                                    Err(e) => scene_status.set(format!("Export failed: {e}")),
+                                }
+                            }
+                        }
+                    >
+                        "Export Scene"
+                    </button>
+                    <textarea
+                        class="scene-export-text"
+                        readonly=true
+                        prop:value=scene_export_text
+                    ></textarea>
+
+                    <textarea
+                        class="scene-import-text"
+                        placeholder="Paste an exported scene JSON here"
+                        prop:value=scene_import_text
+                        on:input=move |ev| scene_import_text.set(event_target_value(&ev))
+                    ></textarea>
+                    <button
+                        class="import-scene-btn"
+                        on:click=move |_| {
+                            let parsed = serde_json::from_str::<serde_json::Value>(&scene_import_text.get())
+                                .map_err(|e| e.to_string())
+                                .and_then(migrate_scene_document)
+                                .and_then(|v| serde_json::from_value::<SceneDocument>(v).map_err(|e| e.to_string()));
+
+                            match parsed {
+                                Ok(doc) => {
+                                    #[cfg(not(feature = "ssr"))]
+                                    match decode_npy_base64(&doc.density_npy_base64) {
+                                        Ok(npy_data) => {
+                                            set_opacities_from_densities(&npy_data.data);
+                                            if let Some(camera) = &doc.camera {
+                                                if let Ok(pose_json) = serde_json::to_string(camera) {
+                                                    set_camera_pose(&pose_json);
+                                                }
+                                            }
+                                            galaxy_count.set(doc.settings.galaxy_count.clone());
+                                            cached_model_output.set(Some(npy_data));
+                                            scene_status.set(format!("Scene imported (format v{}).", doc.format_version));
+                                        }
+                                        Err(e) => scene_status.set(format!("Import failed: {e}")),
+                                    }
+                                }
+                                Err(e) => scene_status.set(format!("Import failed: {e}")),
+                            }
+                        }
+                    >
+                        "Import Scene"
+                    </button>
+                    <p class="scene-status">{move || scene_status.get()}</p>
+                </div>
+
+                <div class="temporal-playback">
+                    <h2>"Temporal Playback"</h2>
+                    <button
+                        class="generate-timeseries-btn"
+                        on:click=move |_| {
+                            temporal_status.set("Generating time series...".to_string());
+                            spawn_local(async move {
+                                let job_ids = match generate_npy_sequence(Some(5), None).await {
+                                    Ok(job_ids) => job_ids,
+                                    Err(e) => {
+                                        temporal_status.set(format!("Error generating snapshots: {:?}", e));
+                                        return;
+                                    }
+                                };
+
+                                temporal_status.set("Running model across snapshots...".to_string());
+                                match run_model_sequence(job_ids.clone(), "model_final.keras".to_string()).await {
+                                    Ok(outputs) => {
+                                        frame_cache.update(|cache| {
+                                            for (step, output) in outputs.into_iter().enumerate() {
+                                                cache.insert(step, output);
+                                            }
+                                        });
+                                        temporal_job_ids.set(job_ids.clone());
+                                        playhead.set(0.0);
+                                        temporal_status.set(format!("{} frames ready", job_ids.len()));
+                                        #[cfg(not(feature = "ssr"))]
+                                        spawn_local(render_playhead(0.0, job_ids, frame_cache));
+                                    }
+                                    Err(e) => {
+                                        temporal_status.set(format!("Error running sequence: {:?}", e));
+                                    }
+                                }
+                            });
+                        }
+                    >
+                        "Generate Time Series"
+                    </button>
+
+                    <input
+                        type="range"
+                        class="timeline-scrubber"
+                        min="0"
+                        max=move || (temporal_job_ids.get().len().max(1) - 1) as f64
+                        step="0.01"
+                        prop:value=playhead
+                        on:input=move |ev| {
+                            if let Ok(value) = event_target_value(&ev).parse::<f64>() {
+                                playhead.set(value);
+                                #[cfg(not(feature = "ssr"))]
+                                spawn_local(render_playhead(value, temporal_job_ids.get(), frame_cache));
+                            }
+                        }
+                    />
+
+                    <button
+                        class="play-pause-btn"
+                        disabled=move || temporal_job_ids.get().len() < 2
+                        on:click=move |_| {
+                            let now_playing = !playing.get();
+                            playing.set(now_playing);
+                            if now_playing {
+                                #[cfg(not(feature = "ssr"))]
+                                spawn_local(playback_loop(temporal_job_ids, frame_cache, playhead, playing));
+                            }
+                        }
+                    >
+                        {move || if playing.get() { "Pause" } else { "Play" }}
+                    </button>
+                    <p class="temporal-status">{move || temporal_status.get()}</p>
+                </div>
+
                 <p class="settings-hint">"Press O to close"</p>
             </div>
         </div>
@@ -668,14 +2524,7 @@ fn HomePage() -> impl IntoView {
                         "The methodology implemented in this simulation follows the UNet-based neural network "
                         "approach detailed in the research by Wang et al. (2024)."
                    </p>
-                   <p class="citation">
-                           "Wang, Z., Shi, F., Yang, X., Li, Q., Liu, Y., & Li, X. (2024). "
-                           <em>"Mapping the large-scale density field of dark matter using artificial intelligence."</em>
-                           " SCIENCE CHINA Physics, Mechanics & Astronomy, 67(1), 219513. "
-                           <a href="https://doi.org/10.1007/s11433-023-2192-9" target="_blank" rel="noopener noreferrer">
-                               "DOI: 10.1007/s11433-023-2192-9"
-                           </a>
-                      </p>
+                   <PaperCard identifier="10.1007/s11433-023-2192-9".to_string() />
                </div>
 
                <div class="team-grid">
@@ -710,27 +2559,328 @@ fn HomePage() -> impl IntoView {
                 <p class="about-hint">"Press I to close"</p>
             </div>
         </div>
+
+        <ChatPanel open=chat_open/>
      }
 }
 
 
+/// Citation card for the About overlay. Fetches live metadata for `identifier`
+/// on mount — from arXiv if it looks like an arXiv ID, otherwise from
+/// Crossref as a DOI — and renders it (title, authors, and abstract) once
+/// available; until then (and on SSR, or if the fetch fails) it shows the
+/// static citation baked in below.
+#[component]
+fn PaperCard(identifier: String) -> impl IntoView {
+    let metadata: RwSignal<Option<CitationMetadata>> = RwSignal::new(None);
+
+    #[cfg(not(feature = "ssr"))]
+    {
+        let identifier = identifier.clone();
+        Effect::new(move |_| {
+            let identifier = identifier.clone();
+            spawn_local(async move {
+                if let Ok(fetched) = fetch_citation_metadata(&identifier).await {
+                    metadata.set(Some(fetched));
+                }
+            });
+        });
+    }
+
+    let doi_for_static = identifier.clone();
+    view! {
+        <p class="citation">
+            {move || match metadata.get() {
+                Some(m) => {
+                    let authors = m.authors.join(", ");
+                    view! {
+                        <span>
+                            {authors} ", (" {m.year} "). " <em>{m.title}</em> ". " {m.journal} ". "
+                            <a href=m.url.clone() target="_blank" rel="noopener noreferrer">
+                                {m.doi.clone()}
+                            </a>
+                            <span class="citation-abstract">{m.abstract_text.clone()}</span>
+                        </span>
+                    }.into_any()
+                }
+                None => {
+                    view! {
+                        <span>
+                            "Wang, Z., Shi, F., Yang, X., Li, Q., Liu, Y., & Li, X. (2024). "
+                            <em>"Mapping the large-scale density field of dark matter using artificial intelligence."</em>
+                            " SCIENCE CHINA Physics, Mechanics & Astronomy, 67(1), 219513. "
+                            <a href=format!("https://doi.org/{}", doi_for_static) target="_blank" rel="noopener noreferrer">
+                                "DOI: " {doi_for_static.clone()}
+                            </a>
+                            <span class="citation-abstract">
+                                "We present a UNet-based neural network that maps sparse, low-resolution tracers of \
+                                 the large-scale structure to the full dark matter density field. The network is \
+                                 trained on N-body simulations spanning the last 10 billion years of cosmic time and \
+                                 recovers filaments and clusters that are not directly observable."
+                            </span>
+                        </span>
+                    }.into_any()
+                }
+            }}
+        </p>
+    }
+}
+
+/// A bundled passage of text the Q&A assistant can retrieve and cite, along
+/// with its precomputed embedding.
+#[cfg(not(feature = "ssr"))]
+#[derive(Clone)]
+struct Passage {
+    source: &'static str,
+    text: String,
+    embedding: Vec<f32>,
+}
+
+/// The corpus backing the in-browser retrieval index: the paper abstract,
+/// a short methodology note, and the UI help text, as (source, text) pairs.
+#[cfg(not(feature = "ssr"))]
+const CORPUS: &[(&str, &str)] = &[
+    (
+        "paper abstract",
+        "We present a UNet-based neural network that maps sparse, low-resolution tracers of the \
+         large-scale structure to the full dark matter density field. The network is trained on \
+         N-body simulations spanning the last 10 billion years of cosmic time and recovers filaments \
+         and clusters that are not directly observable.",
+    ),
+    (
+        "methodology",
+        "Input galaxy positions are voxelized onto a 3D grid and, above a resolution threshold, kept \
+         as a sparse coordinate list to save memory. The UNet model runs server-side in an embedded \
+         Python interpreter and returns a dense density grid, which the client interpolates between \
+         snapshots for smooth temporal playback.",
+    ),
+    (
+        "ui help",
+        "Press O to open simulation settings, where you can place galaxies, run the model, and \
+         scrub through a generated time series. Press I to open the About overlay with the paper \
+         citation and team credits. Press C to open this assistant.",
+    ),
+];
+
+/// Fixed dimensionality for the hashing-trick bag-of-words embeddings used by
+/// the retrieval index. Small and dependency-free, not a learned model.
+#[cfg(not(feature = "ssr"))]
+const EMBED_DIM: usize = 256;
+
+#[cfg(not(feature = "ssr"))]
+fn embed_text(text: &str) -> Vec<f32> {
+    let mut embedding = vec![0f32; EMBED_DIM];
+    for word in text.to_lowercase().split_whitespace() {
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        std::hash::Hash::hash(&word, &mut hasher);
+        let bucket = (std::hash::Hasher::finish(&hasher) as usize) % EMBED_DIM;
+        embedding[bucket] += 1.0;
+    }
+    let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in embedding.iter_mut() {
+            *x /= norm;
+        }
+    }
+    embedding
+}
+
+#[cfg(not(feature = "ssr"))]
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+/// Splits the bundled corpus into sentence-level passages and embeds each one.
+/// Built once on first use and kept in memory for the life of the page.
+#[cfg(not(feature = "ssr"))]
+fn retrieval_index() -> &'static Vec<Passage> {
+    static INDEX: std::sync::LazyLock<Vec<Passage>> = std::sync::LazyLock::new(|| {
+        CORPUS
+            .iter()
+            .flat_map(|(source, text)| {
+                text.split(". ").filter(|s| !s.trim().is_empty()).map(move |sentence| {
+                    let sentence = sentence.trim().trim_end_matches('.').to_string();
+                    Passage { source, embedding: embed_text(&sentence), text: sentence }
+                })
+            })
+            .collect()
+    });
+    &INDEX
+}
+
+/// Retrieves the `k` passages most similar to `query` by cosine similarity.
+#[cfg(not(feature = "ssr"))]
+fn retrieve_top_k(query: &str, k: usize) -> Vec<Passage> {
+    let query_embedding = embed_text(query);
+    let mut scored: Vec<(f32, &Passage)> =
+        retrieval_index().iter().map(|p| (cosine_similarity(&query_embedding, &p.embedding), p)).collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored.into_iter().take(k).map(|(_, p)| p.clone()).collect()
+}
+
+/// Streams a chat completion from an OpenAI-compatible endpoint, calling
+/// `on_token` with each incremental chunk of assistant text as it arrives.
+#[cfg(not(feature = "ssr"))]
+async fn stream_llm_completion(
+    base_url: &str,
+    api_key: &str,
+    prompt: &str,
+    on_token: impl Fn(String),
+) -> Result<(), String> {
+    let url = format!("{}/chat/completions", base_url.trim_end_matches('/'));
+    let resp = reqwest::Client::new()
+        .post(&url)
+        .bearer_auth(api_key)
+        .json(&serde_json::json!({
+            "model": "gpt-4o-mini",
+            "stream": true,
+            "messages": [{"role": "user", "content": prompt}],
+        }))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let mut stream = futures_util::StreamExt::fuse(resp.bytes_stream());
+    let mut buf = String::new();
+    while let Some(chunk) = futures_util::StreamExt::next(&mut stream).await {
+        let chunk = chunk.map_err(|e| e.to_string())?;
+        buf.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(line_end) = buf.find('\n') {
+            let line = buf[..line_end].trim().to_string();
+            buf.drain(..=line_end);
+            let Some(data) = line.strip_prefix("data: ") else { continue };
+            if data == "[DONE]" {
+                return Ok(());
+            }
+            if let Ok(json) = serde_json::from_str::<serde_json::Value>(data) {
+                if let Some(token) = json["choices"][0]["delta"]["content"].as_str() {
+                    on_token(token.to_string());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Retrieval-augmented Q&A assistant about the methodology and the UI, kept
+/// entirely client-side: the retrieval index, embeddings, and LLM call never
+/// touch the server.
+#[component]
+fn ChatPanel(open: RwSignal<bool>) -> impl IntoView {
+    let question = RwSignal::new(String::new());
+    let answer = RwSignal::new(String::new());
+    let sources: RwSignal<Vec<String>> = RwSignal::new(Vec::new());
+    let asking = RwSignal::new(false);
+    let base_url = RwSignal::new("https://api.openai.com/v1".to_string());
+    let api_key = RwSignal::new(String::new());
+
+    #[cfg(not(feature = "ssr"))]
+    let ask = move || {
+        let q = question.get();
+        if q.trim().is_empty() || asking.get() {
+            return;
+        }
+        asking.set(true);
+        answer.set(String::new());
+        let base_url = base_url.get();
+        let api_key = api_key.get();
+        spawn_local(async move {
+            let passages = retrieve_top_k(&q, 3);
+            sources.set(passages.iter().map(|p| format!("{}: {}", p.source, p.text)).collect());
+
+            let context =
+                passages.iter().map(|p| format!("[{}] {}", p.source, p.text)).collect::<Vec<_>>().join("\n");
+            let prompt = format!(
+                "Answer the question using only the context below. Cite sources by name.\n\nContext:\n{context}\n\nQuestion: {q}"
+            );
+
+            let result = stream_llm_completion(&base_url, &api_key, &prompt, |token| {
+                answer.update(|a| a.push_str(&token));
+            })
+            .await;
+            if let Err(e) = result {
+                answer.set(format!("Error: {e}"));
+            }
+            asking.set(false);
+        });
+    };
+
+    #[cfg(feature = "ssr")]
+    let ask = move || {};
+
+    view! {
+        <div class="chat-overlay" class:open=move || open.get()>
+            <div class="chat-panel">
+                <h1 class="chat-title">"Ask about the methodology"</h1>
+
+                <div class="chat-config">
+                    <input
+                        type="text"
+                        class="chat-base-url"
+                        placeholder="LLM base URL"
+                        prop:value=base_url
+                        on:input=move |ev| base_url.set(event_target_value(&ev))
+                    />
+                    <input
+                        type="password"
+                        class="chat-api-key"
+                        placeholder="API key"
+                        prop:value=api_key
+                        on:input=move |ev| api_key.set(event_target_value(&ev))
+                    />
+                </div>
+
+                <input
+                    type="text"
+                    class="chat-question"
+                    placeholder="e.g. how does temporal playback work?"
+                    prop:value=question
+                    on:input=move |ev| question.set(event_target_value(&ev))
+                    on:keydown=move |ev| if ev.key() == "Enter" { ask() }
+                />
+                <button class="chat-ask-btn" disabled=move || asking.get() on:click=move |_| ask()>
+                    {move || if asking.get() { "Thinking..." } else { "Ask" }}
+                </button>
+
+                <p class="chat-answer">{move || answer.get()}</p>
+
+                <div class="chat-sources">
+                    <For each=move || sources.get().into_iter().enumerate() key=|(i, _)| *i let:item>
+                        <p class="chat-source">{item.1}</p>
+                    </For>
+                </div>
+
+                <button class="back-btn" on:click=move |_| open.set(false)>
+                    "Back"
+                </button>
+                <p class="chat-hint">"Press C to close"</p>
+            </div>
+        </div>
+    }
+}
+
 #[component]
-fn DarkMatterScene() -> impl IntoView {
+fn DarkMatterScene(
+    #[prop(default = "scene-canvas".to_string())] canvas_id: String,
+    #[prop(default = "scene-container".to_string())] container_id: String,
+) -> impl IntoView {
     let canvas_ref = NodeRef::<leptos::html::Canvas>::new();
 
     #[cfg(not(feature = "ssr"))]
     {
         let canvas_ref = canvas_ref.clone();
+        let canvas_id = canvas_id.clone();
+        let container_id = container_id.clone();
         Effect::new(move |_| {
             if canvas_ref.get().is_some() {
-                init_scene("scene-canvas", "scene-container");
+                init_scene(&canvas_id, &container_id);
             }
         });
     }
 
     view! {
-        <div id="scene-container" class="container">
-            <canvas id="scene-canvas" node_ref=canvas_ref></canvas>
+        <div id=container_id.clone() class="container">
+            <canvas id=canvas_id node_ref=canvas_ref></canvas>
         </div>
     }
 }
@@ -748,3 +2898,84 @@ fn NotFound() -> impl IntoView {
         <h1>"Not Found"</h1>
     }
 }
+
+#[cfg(all(test, feature = "ssr"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_npy_header_dict_parses_descr_and_shape() {
+        let header = "{'descr': '<f4', 'fortran_order': False, 'shape': (64, 64, 64), }";
+        let (descr, shape) = parse_npy_header_dict(header).unwrap();
+        assert_eq!(descr, "<f4");
+        assert_eq!(shape, vec![64, 64, 64]);
+    }
+
+    #[test]
+    fn parse_npy_header_dict_rejects_fortran_order() {
+        let header = "{'descr': '<f4', 'fortran_order': True, 'shape': (4, 4), }";
+        assert!(parse_npy_header_dict(header).is_err());
+    }
+
+    #[test]
+    fn drain_f32_le_leaves_partial_group_in_tail() {
+        let mut data = Vec::new();
+        // One complete f32 (1.0) plus 3 trailing bytes of a second one.
+        let mut tail: Vec<u8> = 1.0f32.to_le_bytes().to_vec();
+        tail.extend_from_slice(&[0x00, 0x00, 0x80]);
+        drain_f32_le(&mut data, &mut tail);
+        assert_eq!(data, vec![1.0]);
+        assert_eq!(tail, vec![0x00, 0x00, 0x80]);
+    }
+
+    #[test]
+    fn drain_f32_le_completes_across_calls() {
+        let mut data = Vec::new();
+        let bytes = 2.5f32.to_le_bytes();
+        let mut tail: Vec<u8> = bytes[..2].to_vec();
+        drain_f32_le(&mut data, &mut tail);
+        assert!(data.is_empty());
+        tail.extend_from_slice(&bytes[2..]);
+        drain_f32_le(&mut data, &mut tail);
+        assert_eq!(data, vec![2.5]);
+        assert!(tail.is_empty());
+    }
+
+    #[test]
+    fn densify_sparse_input_rebuilds_dense_grid_from_coo() {
+        let job_id = format!("test-densify-{:08x}", rand::random::<u32>());
+        let dir = job_dir(&job_id);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let coords: Vec<u64> = vec![0, 0, 0, 1, 1, 1];
+        let values: Vec<f32> = vec![42.0, 7.0];
+        write_npy(&dir.join("input_coords.npy"), &[2, 3], &coords).unwrap();
+        write_npy(&dir.join("input_values.npy"), &[2], &values).unwrap();
+        write_npy(&dir.join("input_resolution.npy"), &[1], &[4u64]).unwrap();
+
+        let (data, shape) = densify_sparse_input(&job_id).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(shape, vec![4, 4, 4]);
+        assert_eq!(data.len(), 64);
+        assert_eq!(data[0], 42.0);
+        assert_eq!(data[1 * 4 * 4 + 1 * 4 + 1], 7.0);
+        assert_eq!(data.iter().filter(|&&d| d == -1.0).count(), 62);
+    }
+
+    #[test]
+    fn shortest_density_path_follows_the_high_density_ridge() {
+        // A 1x1x3 corridor: the middle cell is dense, so the path should
+        // still just be the only straight line from one end to the other.
+        let densities = vec![1.0, 100.0, 1.0];
+        let path = shortest_density_path(&densities, (1, 1, 3), 0, 2).unwrap();
+        assert_eq!(path, vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn shortest_density_path_is_single_cell_when_source_equals_target() {
+        let densities = vec![1.0];
+        let path = shortest_density_path(&densities, (1, 1, 1), 0, 0).unwrap();
+        assert_eq!(path, vec![0]);
+    }
+}